@@ -0,0 +1,400 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    units::format_size,
+    filetype::FileType,
+    i18n::{tr, tr_fmt},
+    junk::{self, JunkRule},
+    scan::{ScanNode, ScanResult, TableEntry},
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+pub fn percentage(size: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        size as f64 / total as f64 * 100.0
+    }
+}
+
+//ファイルだけをフルパス付きで順に訪問する
+pub fn walk_files<F>(node: &ScanNode, path: &Path, f: &mut F)
+where
+    F: FnMut(&Path, &ScanNode),
+{
+    for child in &node.children {
+        let child_path = path.join(&child.name);
+        if child.is_dir {
+            walk_files(child, &child_path, f);
+        } else {
+            f(&child_path, child);
+        }
+    }
+}
+
+pub fn extension_of(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeFilter {
+    Category(FileType),
+    Extension(String),
+}
+
+impl TypeFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            TypeFilter::Category(file_type) => FileType::from_name(name) == *file_type,
+            TypeFilter::Extension(ext) => extension_of(name) == *ext,
+        }
+    }
+}
+
+impl Display for TypeFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeFilter::Category(file_type) => write!(f, "{}", file_type),
+            TypeFilter::Extension(ext) if ext.is_empty() => write!(f, "{}", tr("filter.no_extension")),
+            TypeFilter::Extension(ext) => write!(f, ".{}", ext),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeGroup {
+    pub filter: TypeFilter,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TypeBreakdown {
+    pub total: u64,
+    pub categories: Vec<TypeGroup>,
+    pub extensions: Vec<TypeGroup>,
+}
+
+impl TypeBreakdown {
+    pub fn percentage(&self, group: &TypeGroup) -> f64 {
+        percentage(group.bytes, self.total)
+    }
+}
+
+pub fn type_breakdown(tree: &ScanNode, root: &Path) -> TypeBreakdown {
+    let mut categories: HashMap<FileType, (u64, u64)> = HashMap::new();
+    let mut extensions: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut total = 0;
+    walk_files(tree, root, &mut |_, node| {
+        total += node.size;
+        let category = categories.entry(FileType::from_name(&node.name)).or_default();
+        category.0 += 1;
+        category.1 += node.size;
+        let extension = extensions.entry(extension_of(&node.name)).or_default();
+        extension.0 += 1;
+        extension.1 += node.size;
+    });
+
+    let mut categories: Vec<TypeGroup> = categories
+        .into_iter()
+        .map(|(file_type, (count, bytes))| TypeGroup {
+            filter: TypeFilter::Category(file_type),
+            count,
+            bytes,
+        })
+        .collect();
+    let mut extensions: Vec<TypeGroup> = extensions
+        .into_iter()
+        .map(|(ext, (count, bytes))| TypeGroup {
+            filter: TypeFilter::Extension(ext),
+            count,
+            bytes,
+        })
+        .collect();
+    categories.sort_by_key(|group| std::cmp::Reverse(group.bytes));
+    extensions.sort_by_key(|group| std::cmp::Reverse(group.bytes));
+
+    TypeBreakdown {
+        total,
+        categories,
+        extensions,
+    }
+}
+
+pub fn files_matching(tree: &ScanNode, root: &Path, filter: &TypeFilter) -> Vec<TableEntry> {
+    let mut files = vec![];
+    walk_files(tree, root, &mut |path, node| {
+        if filter.matches(&node.name) {
+            files.push((path.to_path_buf(), node.size, node.modified, false));
+        }
+    });
+    files
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AgeBucket {
+    Day,
+    Week,
+    Month,
+    Year,
+    Older,
+    Unknown,
+}
+
+impl AgeBucket {
+    pub const ALL: &'static [Self] = &[Self::Day, Self::Week, Self::Month, Self::Year, Self::Older, Self::Unknown];
+
+    //未来の日時は最新として扱う
+    pub fn of(modified: Option<SystemTime>, now: SystemTime) -> Self {
+        let Some(modified) = modified else {
+            return Self::Unknown;
+        };
+        let age = now.duration_since(modified).unwrap_or_default();
+        if age < Duration::from_secs(DAY) {
+            Self::Day
+        } else if age < Duration::from_secs(7 * DAY) {
+            Self::Week
+        } else if age < Duration::from_secs(30 * DAY) {
+            Self::Month
+        } else if age < Duration::from_secs(365 * DAY) {
+            Self::Year
+        } else {
+            Self::Older
+        }
+    }
+}
+
+impl Display for AgeBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgeBucket::Day => tr("age.day"),
+            AgeBucket::Week => tr("age.week"),
+            AgeBucket::Month => tr("age.month"),
+            AgeBucket::Year => tr("age.year"),
+            AgeBucket::Older => tr("age.older"),
+            AgeBucket::Unknown => tr("age.unknown"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AgeGroup {
+    pub bucket: AgeBucket,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AgeHistogram {
+    pub total: u64,
+    pub groups: Vec<AgeGroup>,
+}
+
+impl AgeHistogram {
+    pub fn percentage(&self, group: &AgeGroup) -> f64 {
+        percentage(group.bytes, self.total)
+    }
+}
+
+pub fn age_histogram(tree: &ScanNode, now: SystemTime) -> AgeHistogram {
+    let mut groups: Vec<AgeGroup> = AgeBucket::ALL
+        .iter()
+        .map(|bucket| AgeGroup {
+            bucket: *bucket,
+            count: 0,
+            bytes: 0,
+        })
+        .collect();
+    let mut total = 0;
+    walk_files(tree, Path::new(""), &mut |_, node| {
+        let bucket = AgeBucket::of(node.modified, now);
+        let group = groups.iter_mut().find(|group| group.bucket == bucket).unwrap();
+        group.count += 1;
+        group.bytes += node.size;
+        total += node.size;
+    });
+    //日時を取得できなかったファイルがなければ項目ごと省く
+    groups.retain(|group| group.bucket != AgeBucket::Unknown || group.count > 0);
+    AgeHistogram { total, groups }
+}
+
+pub const STALE_DAYS: &[u64] = &[30, 90, 180, 365, 730, 1825];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinSize(pub u64);
+
+impl MinSize {
+    pub const ALL: &'static [Self] = &[
+        Self(0),
+        Self(1024 * 1024),
+        Self(10 * 1024 * 1024),
+        Self(100 * 1024 * 1024),
+        Self(1024 * 1024 * 1024),
+    ];
+}
+
+impl Display for MinSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_size(self.0))
+    }
+}
+
+//指定日数以上更新されておらず、指定サイズ以上のファイルを大きい順に返す
+pub fn stale_files(
+    tree: &ScanNode,
+    root: &Path,
+    now: SystemTime,
+    days: u64,
+    min_size: u64,
+) -> Vec<TableEntry> {
+    let threshold = now.checked_sub(Duration::from_secs(days * DAY)).unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut files = vec![];
+    walk_files(tree, root, &mut |path, node| {
+        if node.size >= min_size && node.modified.is_some_and(|modified| modified <= threshold) {
+            files.push((path.to_path_buf(), node.size, node.modified, false));
+        }
+    });
+    files.sort_by_key(|(_, size, _, _)| std::cmp::Reverse(*size));
+    files
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmptyEntries {
+    pub dirs: Vec<PathBuf>,
+    pub files: Vec<PathBuf>,
+}
+
+impl EmptyEntries {
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty() && self.files.is_empty()
+    }
+}
+
+//配下にファイルが一つもないフォルダと0バイトのファイルを集める
+//空フォルダが連なっている場合は一番上のフォルダだけを返す
+pub fn empty_entries(tree: &ScanNode, root: &Path) -> EmptyEntries {
+    let mut entries = EmptyEntries::default();
+    collect_empty(tree, root, &mut entries);
+    entries
+}
+
+fn collect_empty(node: &ScanNode, path: &Path, entries: &mut EmptyEntries) {
+    for child in &node.children {
+        let child_path = path.join(&child.name);
+        if !child.is_dir {
+            if child.size == 0 {
+                entries.files.push(child_path);
+            }
+        } else if child.file_count() == 0 {
+            entries.dirs.push(child_path);
+        } else {
+            collect_empty(child, &child_path, entries);
+        }
+    }
+}
+
+pub const LARGEST_COUNTS: &[usize] = &[10, 50, 100, 500, 1000];
+
+//大きい順にn件だけを残すヒープ。ツリーを辿る場合もディスクを直接辿る場合も使う
+pub struct LargestFiles {
+    heap: BinaryHeap<Reverse<(u64, PathBuf, Option<SystemTime>)>>,
+    n: usize,
+}
+
+impl LargestFiles {
+    pub fn new(n: usize) -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(n + 1),
+            n,
+        }
+    }
+
+    //パスを作る前に、このサイズならヒープに入るかを確かめる
+    pub fn accepts(&self, size: u64) -> bool {
+        self.heap.len() < self.n || self.heap.peek().is_some_and(|Reverse((smallest, _, _))| size > *smallest)
+    }
+
+    pub fn push(&mut self, size: u64, path: PathBuf, modified: Option<SystemTime>) {
+        if !self.accepts(size) {
+            return;
+        }
+        if self.heap.len() == self.n {
+            self.heap.pop();
+        }
+        self.heap.push(Reverse((size, path, modified)));
+    }
+
+    pub fn into_entries(self) -> Vec<TableEntry> {
+        self.heap.into_sorted_vec().into_iter().map(|Reverse((size, path, time))| (path, size, time, false)).collect()
+    }
+}
+
+//スキャン済みのツリーを辿り、大きい順にn件を返す。パスはヒープに入るものだけ作る
+pub fn largest_files(tree: &ScanNode, root: &Path, n: usize) -> Vec<TableEntry> {
+    let mut largest = LargestFiles::new(n);
+    largest_in(tree, &mut root.to_path_buf(), &mut largest);
+    largest.into_entries()
+}
+
+fn largest_in(node: &ScanNode, path: &mut PathBuf, largest: &mut LargestFiles) {
+    for child in &node.children {
+        if child.is_dir {
+            path.push(&child.name);
+            largest_in(child, path, largest);
+            path.pop();
+        } else if largest.accepts(child.size) {
+            largest.push(child.size, path.join(&child.name), child.modified);
+        }
+    }
+}
+
+//テーブルをツリー全体からの抽出結果に切り替える条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableFilter {
+    Type(TypeFilter),
+    Stale { dir: PathBuf, days: u64, min_size: u64 },
+    Largest(usize),
+    Junk { rules: Vec<JunkRule>, rule: usize },
+}
+
+impl TableFilter {
+    pub fn files(&self, result: &ScanResult, now: SystemTime) -> Vec<TableEntry> {
+        match self {
+            TableFilter::Type(filter) => files_matching(&result.tree, &result.root, filter),
+            TableFilter::Stale { dir, days, min_size } => match result.node(dir) {
+                Some(node) => stale_files(node, dir, now, *days, *min_size),
+                None => vec![],
+            },
+            TableFilter::Largest(n) => largest_files(&result.tree, &result.root, *n),
+            TableFilter::Junk { rules, rule } => junk::detect(&result.tree, &result.root, rules)
+                .into_iter()
+                .filter(|found| found.rule == *rule)
+                .map(|found| (found.path, found.size, found.modified, found.is_dir))
+                .collect(),
+        }
+    }
+}
+
+impl Display for TableFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableFilter::Type(filter) => write!(f, "{}", filter),
+            TableFilter::Stale { days, min_size, .. } => {
+                write!(f, "{}", tr_fmt("filter.stale", &[days, &format_size(*min_size)]))
+            }
+            TableFilter::Largest(n) => write!(f, "{}", tr_fmt("filter.largest", &[n])),
+            TableFilter::Junk { rules, rule } => write!(f, "{}", rules[*rule].name),
+        }
+    }
+}
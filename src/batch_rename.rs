@@ -0,0 +1,324 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+
+use crate::{error::Error, file, i18n::tr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseConversion {
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+impl CaseConversion {
+    pub const ALL: &'static [Self] = &[Self::Keep, Self::Lower, Self::Upper, Self::Title];
+
+    fn apply(&self, value: &str) -> String {
+        match self {
+            CaseConversion::Keep => value.to_string(),
+            CaseConversion::Lower => value.to_lowercase(),
+            CaseConversion::Upper => value.to_uppercase(),
+            CaseConversion::Title => {
+                let mut result = String::with_capacity(value.len());
+                let mut start = true;
+                for c in value.chars() {
+                    if start {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.extend(c.to_lowercase());
+                    }
+                    start = !c.is_alphanumeric();
+                }
+                result
+            }
+        }
+    }
+}
+
+impl Display for CaseConversion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaseConversion::Keep => tr("case.keep"),
+            CaseConversion::Lower => tr("case.lower"),
+            CaseConversion::Upper => tr("case.upper"),
+            CaseConversion::Title => tr("case.title"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchRenameOptions {
+    pub find: String,
+    pub replace: String,
+    pub use_regex: bool,
+    //{name} {ext} {n} {n:03} {date} {date:%Y%m%d}
+    pub template: String,
+    pub case: CaseConversion,
+    pub start: usize,
+}
+
+impl Default for BatchRenameOptions {
+    fn default() -> Self {
+        Self {
+            find: String::new(),
+            replace: String::new(),
+            use_regex: false,
+            template: String::from("{name}"),
+            case: CaseConversion::Keep,
+            start: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewStatus {
+    Ok,
+    Unchanged,
+    Invalid,
+    Collision,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenamePreview {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub status: PreviewStatus,
+}
+
+impl RenamePreview {
+    pub fn new_name(&self) -> String {
+        self.to
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+pub fn preview(paths: &[PathBuf], options: &BatchRenameOptions) -> Result<Vec<RenamePreview>, Error> {
+    let regex = if options.use_regex && !options.find.is_empty() {
+        Some(Regex::new(&options.find).map_err(|e| Error::InvalidPattern(e.to_string()))?)
+    } else {
+        None
+    };
+
+    let mut previews = vec![];
+    for (i, path) in paths.iter().enumerate() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let replaced = match &regex {
+            Some(regex) => regex.replace_all(&name, options.replace.as_str()).into_owned(),
+            None if !options.find.is_empty() => name.replace(&options.find, &options.replace),
+            None => name.clone(),
+        };
+
+        let (stem, ext) = split_name(&replaced);
+        let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        let mut new_name = expand_template(&options.template, stem, ext, options.start + i, modified)?;
+        if !ext.is_empty() && !options.template.contains("{ext}") {
+            new_name = format!("{}.{}", new_name, ext);
+        }
+        let new_name = options.case.apply(&new_name);
+
+        let status = if new_name == name {
+            PreviewStatus::Unchanged
+        } else if file::check_name(&new_name).is_err() {
+            PreviewStatus::Invalid
+        } else {
+            PreviewStatus::Ok
+        };
+        previews.push(RenamePreview {
+            from: path.clone(),
+            to: path.with_file_name(new_name),
+            status,
+        });
+    }
+
+    mark_collisions(&mut previews);
+    Ok(previews)
+}
+
+//バッチ内での重複と、バッチ外の既存エントリとの衝突を検出する
+fn mark_collisions(previews: &mut [RenamePreview]) {
+    let sources: HashSet<PathBuf> = previews.iter().map(|p| p.from.clone()).collect();
+    let mut seen = HashSet::new();
+    let mut duplicated = HashSet::new();
+    for preview in previews.iter() {
+        if !seen.insert(preview.to.clone()) {
+            duplicated.insert(preview.to.clone());
+        }
+    }
+    for preview in previews.iter_mut() {
+        if preview.status != PreviewStatus::Ok {
+            continue;
+        }
+        //大文字小文字だけの変更では変更先が変更元自身を指すので衝突ではない
+        let exists = std::fs::symlink_metadata(&preview.to).is_ok()
+            && !sources.contains(&preview.to)
+            && !file::is_same_entry(&preview.from, &preview.to);
+        if duplicated.contains(&preview.to) || exists {
+            preview.status = PreviewStatus::Collision;
+        }
+    }
+}
+
+pub fn can_apply(previews: &[RenamePreview]) -> bool {
+    previews.iter().any(|p| p.status == PreviewStatus::Ok)
+        && previews
+            .iter()
+            .all(|p| matches!(p.status, PreviewStatus::Ok | PreviewStatus::Unchanged))
+}
+
+fn split_name(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(index) if index > 0 => (&name[..index], &name[index + 1..]),
+        _ => (name, ""),
+    }
+}
+
+fn expand_template(
+    template: &str,
+    stem: &str,
+    ext: &str,
+    n: usize,
+    modified: Option<SystemTime>,
+) -> Result<String, Error> {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        result.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .map(|close| open + close)
+            .ok_or_else(|| Error::InvalidPattern(template.into()))?;
+        let token = &rest[open + 1..close];
+        let (key, spec) = match token.split_once(':') {
+            Some((key, spec)) => (key, Some(spec)),
+            None => (token, None),
+        };
+        match key {
+            "name" => result.push_str(stem),
+            "ext" => result.push_str(ext),
+            "n" => {
+                let width = match spec {
+                    Some(spec) => spec
+                        .parse::<usize>()
+                        .map_err(|_| Error::InvalidPattern(token.into()))?,
+                    None => 0,
+                };
+                result.push_str(&format!("{:0width$}", n, width = width));
+            }
+            "date" => {
+                let datetime: DateTime<Local> = DateTime::from(modified.unwrap_or(SystemTime::UNIX_EPOCH));
+                let mut formatted = String::new();
+                write!(formatted, "{}", datetime.format(spec.unwrap_or("%Y%m%d")))
+                    .map_err(|_| Error::InvalidPattern(token.into()))?;
+                result.push_str(&formatted);
+            }
+            _ => return Err(Error::InvalidPattern(token.into())),
+        }
+        rest = &rest[close + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+pub async fn apply(previews: Vec<RenamePreview>) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    let plan: Vec<(PathBuf, PathBuf)> = previews
+        .into_iter()
+        .filter(|p| p.status == PreviewStatus::Ok)
+        .map(|p| (p.from, p.to))
+        .collect();
+
+    //a->b, b->a のような入れ替えに備え、変更先が別の変更元と重なる場合は一時名を経由する
+    let sources: HashSet<&Path> = plan.iter().map(|(from, _)| from.as_path()).collect();
+    let chained = plan.iter().any(|(_, to)| sources.contains(to.as_path()));
+
+    //途中で失敗したら、それまでの変更を逆順に戻してから返す
+    let mut done = vec![];
+    if let Err(e) = rename_all(&plan, chained, &mut done).await {
+        for (current, original) in done.iter().rev() {
+            let _ = tokio::fs::rename(current, original).await;
+        }
+        return Err(e);
+    }
+    Ok(plan)
+}
+
+async fn rename_all(
+    plan: &[(PathBuf, PathBuf)],
+    chained: bool,
+    done: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), Error> {
+    let mut staged = vec![];
+    for (i, (from, to)) in plan.iter().enumerate() {
+        if chained {
+            let temp = file::unique_path(&from.with_file_name(format!(".frsm_rename_{}", i)));
+            tokio::fs::rename(from, &temp)
+                .await
+                .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+            done.push((temp.clone(), from.clone()));
+            staged.push((temp, to.clone()));
+        } else {
+            staged.push((from.clone(), to.clone()));
+        }
+    }
+    for (from, to) in &staged {
+        if !chained && std::fs::symlink_metadata(to).is_ok() && !file::is_same_entry(from, to) {
+            return Err(Error::AlreadyExists(to.display().to_string()));
+        }
+        tokio::fs::rename(from, to)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        done.push((to.clone(), from.clone()));
+    }
+    Ok(())
+}
+
+pub struct BatchRenameState {
+    pub sources: Vec<PathBuf>,
+    pub options: BatchRenameOptions,
+    pub previews: Vec<RenamePreview>,
+    pub error: Option<String>,
+}
+
+impl BatchRenameState {
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        let mut state = Self {
+            sources,
+            options: BatchRenameOptions::default(),
+            previews: vec![],
+            error: None,
+        };
+        state.refresh();
+        state
+    }
+
+    //オプションが変わるたびにプレビューを作り直す
+    pub fn refresh(&mut self) {
+        match preview(&self.sources, &self.options) {
+            Ok(previews) => {
+                self.previews = previews;
+                self.error = None;
+            }
+            Err(e) => {
+                self.previews.clear();
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn can_apply(&self) -> bool {
+        self.error.is_none() && can_apply(&self.previews)
+    }
+}
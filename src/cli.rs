@@ -0,0 +1,231 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    export::{self, ExportEntry, ExportFormat},
+    i18n::tr,
+    scan::{self, ScanNode, TableEntry},
+    settings::AppSettings,
+};
+
+pub const USAGE: &str = "usage: filersmanager scan <path> [--format json|csv|tsv] [--depth N] [--top N] [--sort size|name|modified] [--largest]
+
+Scans <path> without opening a window and writes the entries to stdout.
+  --format   output format (default: tsv)
+  --depth    how many levels below <path> to list (default: 1)
+  --top      only print the first N entries after sorting
+  --sort     sort order (default: size, largest first)
+  --largest  list the largest files anywhere under <path> instead (--top sets how many, default: 10)";
+
+//--largest で --top を指定しなかったときの件数
+const LARGEST_DEFAULT: usize = 10;
+
+//GUIのテーブルの並び順にも使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Size,
+    Name,
+    Modified,
+}
+
+impl SortKey {
+    pub const ALL: &'static [Self] = &[Self::Size, Self::Name, Self::Modified];
+
+    pub fn sort(&self, entries: &mut [TableEntry]) {
+        entries.sort_by(|a, b| self.compare((&a.0, a.1, a.2), (&b.0, b.1, b.2)));
+    }
+
+    //サイズと日時は大きい(新しい)順、名前は昇順
+    pub fn compare(&self, a: (&Path, u64, Option<SystemTime>), b: (&Path, u64, Option<SystemTime>)) -> Ordering {
+        match self {
+            SortKey::Size => b.1.cmp(&a.1),
+            SortKey::Name => a.0.cmp(b.0),
+            SortKey::Modified => b.2.cmp(&a.2),
+        }
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Size => tr("sort.size"),
+            SortKey::Name => tr("sort.name"),
+            SortKey::Modified => tr("sort.modified"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanCommand {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    pub depth: usize,
+    pub top: Option<usize>,
+    pub sort: SortKey,
+    pub largest: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    Help,
+    Usage(String),
+    Failed(String),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<Error> for CliError {
+    fn from(e: Error) -> Self {
+        CliError::Failed(e.to_string())
+    }
+}
+
+//サブコマンドがなければGUIを起動するのでNoneを返す
+//ファイルの関連付けや「プログラムから開く」ではパスだけが渡されるので、それもGUIで開く
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<ScanCommand>, CliError> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("scan") => {}
+        Some("-h") | Some("--help") => return Err(CliError::Help),
+        _ => return Ok(None),
+    }
+
+    let mut path = None;
+    let mut command = ScanCommand {
+        path: PathBuf::new(),
+        format: ExportFormat::Tsv,
+        depth: 1,
+        top: None,
+        sort: SortKey::Size,
+        largest: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| CliError::Usage(format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--format" => {
+                let value = value()?;
+                command.format = ExportFormat::ALL
+                    .iter()
+                    .copied()
+                    .find(|format| format.extension() == value)
+                    .ok_or_else(|| CliError::Usage(format!("unknown format: {}", value)))?;
+            }
+            "--depth" => command.depth = number(&arg, &value()?)?,
+            "--top" => command.top = Some(number(&arg, &value()?)?),
+            "--largest" => command.largest = true,
+            "--sort" => {
+                command.sort = match value()?.as_str() {
+                    "size" => SortKey::Size,
+                    "name" => SortKey::Name,
+                    "modified" => SortKey::Modified,
+                    other => return Err(CliError::Usage(format!("unknown sort key: {}", other))),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(CliError::Usage(format!("unknown option: {}", arg))),
+            _ if path.is_none() => path = Some(PathBuf::from(arg)),
+            _ => return Err(CliError::Usage(format!("unexpected argument: {}", arg))),
+        }
+    }
+    command.path = path.ok_or_else(|| CliError::Usage("missing <path>".into()))?;
+    Ok(Some(command))
+}
+
+fn number(option: &str, value: &str) -> Result<usize, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("{} expects a number, got {}", option, value)))
+}
+
+//指定した深さまでのエントリを並べ替えて返す
+pub fn collect(
+    tree: &ScanNode,
+    root: &Path,
+    depth: usize,
+    sort: SortKey,
+    top: Option<usize>,
+) -> Vec<TableEntry> {
+    let mut entries = vec![];
+    collect_in(tree, root, depth, &mut entries);
+    sort.sort(&mut entries);
+    if let Some(top) = top {
+        entries.truncate(top);
+    }
+    entries
+}
+
+fn collect_in(node: &ScanNode, path: &Path, depth: usize, entries: &mut Vec<TableEntry>) {
+    if depth == 0 {
+        return;
+    }
+    for child in &node.children {
+        let child_path = path.join(&child.name);
+        entries.push((child_path.clone(), child.size, child.modified, child.is_dir));
+        if child.is_dir {
+            collect_in(child, &child_path, depth - 1, entries);
+        }
+    }
+}
+
+//リリースビルドのWindowsはコンソールを持たないので、起動元のコンソールに標準出力をつなぐ
+#[cfg(windows)]
+pub fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+pub fn run(command: &ScanCommand, out: &mut impl Write) -> Result<(), CliError> {
+    if !command.path.is_dir() {
+        return Err(CliError::Failed(format!("not a directory: {}", command.path.display())));
+    }
+    //画面から開いたときと同じ結果になるよう、保存してある除外パターンを使う
+    let exclude = AppSettings::load_read_only().exclude;
+    let entries = if command.largest {
+        //ツリーを作らずに辿るので、大きなフォルダでもメモリは件数分しか使わない
+        let options = scan::ScanOptions {
+            exclude,
+            one_file_system: false,
+        };
+        let n = command.top.unwrap_or(LARGEST_DEFAULT);
+        let mut entries = iced::futures::executor::block_on(scan::largest_files(command.path.clone(), n, options));
+        command.sort.sort(&mut entries);
+        entries
+    } else {
+        let result = iced::futures::executor::block_on(scan::scan_excluding(command.path.clone(), exclude));
+        collect(&result.tree, &result.root, command.depth, command.sort, command.top)
+    };
+    let entries: Vec<ExportEntry> = entries
+        .into_iter()
+        .map(|(path, bytes, _, _)| ExportEntry::new(path, bytes))
+        .collect();
+    let text = export::render(command.format, &entries)?;
+    out.write_all(text.as_bytes())
+        .map_err(|e| CliError::Failed(e.to_string()))
+}
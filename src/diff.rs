@@ -0,0 +1,130 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    error::Error,
+    i18n::tr,
+    scan::{ScanNode, ScanResult},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::Added => tr("diff.added"),
+            ChangeKind::Removed => tr("diff.removed"),
+            ChangeKind::Changed => tr("diff.changed"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub kind: ChangeKind,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub old_files: u64,
+    pub new_files: u64,
+}
+
+impl DiffEntry {
+    pub fn delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+
+    pub fn file_delta(&self) -> i64 {
+        self.new_files as i64 - self.old_files as i64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    pub old_scanned_at: SystemTime,
+    pub new_scanned_at: SystemTime,
+    pub root: DiffEntry,
+    pub entries: Vec<DiffEntry>,
+}
+
+//同じフォルダを別の時点でスキャンした結果を比べ、変化の大きい順に並べる
+pub fn diff(old: &ScanResult, new: &ScanResult) -> Result<DiffReport, Error> {
+    if old.root != new.root {
+        return Err(Error::DifferentRoots(old.root.clone(), new.root.clone()));
+    }
+    let mut entries = vec![];
+    compare(&old.tree, &new.tree, &new.root, &mut entries);
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.delta().unsigned_abs()));
+    Ok(DiffReport {
+        old_scanned_at: old.scanned_at,
+        new_scanned_at: new.scanned_at,
+        root: changed(&old.tree, &new.tree, new.root.clone()),
+        entries,
+    })
+}
+
+fn changed(old: &ScanNode, new: &ScanNode, path: PathBuf) -> DiffEntry {
+    DiffEntry {
+        path,
+        is_dir: new.is_dir,
+        kind: ChangeKind::Changed,
+        old_size: old.size,
+        new_size: new.size,
+        old_files: old.file_count(),
+        new_files: new.file_count(),
+    }
+}
+
+//追加・削除されたフォルダはその中まで列挙せず一つの項目にまとめる
+fn compare(old: &ScanNode, new: &ScanNode, path: &Path, entries: &mut Vec<DiffEntry>) {
+    let previous: HashMap<(&str, bool), &ScanNode> =
+        old.children.iter().map(|child| ((child.name.as_str(), child.is_dir), child)).collect();
+    let current: HashSet<(&str, bool)> = new.children.iter().map(|child| (child.name.as_str(), child.is_dir)).collect();
+    for child in &new.children {
+        let child_path = path.join(&child.name);
+        match previous.get(&(child.name.as_str(), child.is_dir)) {
+            Some(previous) => {
+                let entry = changed(previous, child, child_path.clone());
+                if entry.delta() != 0 || entry.file_delta() != 0 {
+                    entries.push(entry);
+                }
+                if child.is_dir {
+                    compare(previous, child, &child_path, entries);
+                }
+            }
+            None => entries.push(DiffEntry {
+                path: child_path,
+                is_dir: child.is_dir,
+                kind: ChangeKind::Added,
+                old_size: 0,
+                new_size: child.size,
+                old_files: 0,
+                new_files: child.file_count(),
+            }),
+        }
+    }
+    for child in &old.children {
+        if !current.contains(&(child.name.as_str(), child.is_dir)) {
+            entries.push(DiffEntry {
+                path: path.join(&child.name),
+                is_dir: child.is_dir,
+                kind: ChangeKind::Removed,
+                old_size: child.size,
+                new_size: 0,
+                old_files: child.file_count(),
+                new_files: 0,
+            });
+        }
+    }
+}
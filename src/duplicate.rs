@@ -0,0 +1,275 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use iced::futures::{channel::mpsc, SinkExt};
+use tokio::io::AsyncReadExt;
+
+use crate::{analysis::walk_files, error::Error, i18n::tr, scan::ScanNode};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+//先頭だけを読む部分ハッシュの大きさ
+const PARTIAL_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub files: Vec<PathBuf>,
+    pub keep: usize,
+    //検索時の内容全体のハッシュ。削除する前に変わっていないか確かめる
+    pub hash: blake3::Hash,
+}
+
+impl DuplicateGroup {
+    //残す一つ以外が無駄になっている容量
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.files.len() as u64).saturating_sub(1)
+    }
+
+    pub fn removable(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().enumerate().filter(|(index, _)| *index != self.keep).map(|(_, path)| path)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    Trash,
+    HardLink,
+}
+
+impl DuplicateAction {
+    pub const ALL: &'static [Self] = &[Self::Trash, Self::HardLink];
+}
+
+impl Display for DuplicateAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DuplicateAction::Trash => tr("duplicates.trash"),
+            DuplicateAction::HardLink => tr("duplicates.hard_link"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateProgress {
+    pub checked: u64,
+    pub total: u64,
+    pub current: PathBuf,
+}
+
+pub struct DuplicateState {
+    pub groups: Vec<DuplicateGroup>,
+    pub action: DuplicateAction,
+    pub running: bool,
+    pub checked: u64,
+    pub total: u64,
+    pub current: PathBuf,
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl DuplicateState {
+    pub fn new() -> Self {
+        Self {
+            groups: vec![],
+            action: DuplicateAction::Trash,
+            running: true,
+            checked: 0,
+            total: 0,
+            current: PathBuf::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.checked as f32 / self.total as f32
+        }
+    }
+
+    pub fn wasted(&self) -> u64 {
+        self.groups.iter().map(DuplicateGroup::wasted).sum()
+    }
+}
+
+impl Default for DuplicateState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//同じサイズのファイルが複数ある組だけを候補にする。空ファイルは対象外
+pub fn group_by_size(tree: &ScanNode, root: &Path) -> Vec<(u64, Vec<PathBuf>)> {
+    let mut sizes: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    walk_files(tree, root, &mut |path, node| {
+        if node.size > 0 {
+            sizes.entry(node.size).or_default().push(path.to_path_buf());
+        }
+    });
+    let mut groups: Vec<(u64, Vec<PathBuf>)> = sizes
+        .into_iter()
+        .map(|(size, files)| (size, collapse_links(files)))
+        .filter(|(_, files)| files.len() > 1)
+        .collect();
+    groups.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+    groups
+}
+
+//既にハードリンクになっているファイルは中身が同じでも容量を使っていないので一つにまとめる
+fn collapse_links(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    if files.len() < 2 {
+        return files;
+    }
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .filter(|path| file_id(path).is_none_or(|id| seen.insert(id)))
+        .collect()
+}
+
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+//サイズ、先頭部分のハッシュ、内容全体のハッシュの順に絞り込む
+pub async fn find(
+    candidates: Vec<(u64, Vec<PathBuf>)>,
+    mut sender: mpsc::Sender<DuplicateProgress>,
+    cancel: Arc<AtomicBool>,
+) -> Result<Vec<DuplicateGroup>, Error> {
+    let total = candidates.iter().map(|(_, files)| files.len() as u64).sum();
+    let mut checked = 0;
+    let mut duplicates = vec![];
+
+    for (size, files) in candidates {
+        let current = files[0].clone();
+        let count = files.len() as u64;
+        let partial = regroup(files, PARTIAL_SIZE, &cancel).await?;
+        for (hash, files) in partial {
+            //先頭部分に収まるファイルは部分ハッシュがそのまま全体のハッシュになる
+            let groups = if size <= PARTIAL_SIZE {
+                vec![(hash, files)]
+            } else {
+                regroup(files, u64::MAX, &cancel).await?
+            };
+            duplicates.extend(groups.into_iter().map(|(hash, mut files)| {
+                files.sort();
+                DuplicateGroup { size, files, keep: 0, hash }
+            }));
+        }
+
+        checked += count;
+        let _ = sender
+            .send(DuplicateProgress {
+                checked,
+                total,
+                current,
+            })
+            .await;
+    }
+    duplicates.sort_by_key(|group| std::cmp::Reverse(group.wasted()));
+    Ok(duplicates)
+}
+
+//ハッシュが一致するファイルごとにまとめ直す。読めないファイルは候補から外す
+async fn regroup(files: Vec<PathBuf>, limit: u64, cancel: &AtomicBool) -> Result<Vec<(blake3::Hash, Vec<PathBuf>)>, Error> {
+    let mut hashes: HashMap<blake3::Hash, Vec<PathBuf>> = HashMap::new();
+    for path in files {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        if let Ok(hash) = hash_file(&path, limit, cancel).await {
+            hashes.entry(hash).or_default().push(path);
+        }
+    }
+    Ok(hashes.into_iter().filter(|(_, files)| files.len() > 1).collect())
+}
+
+pub async fn hash_file(path: &Path, limit: u64, cancel: &AtomicBool) -> Result<blake3::Hash, Error> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut remaining = limit;
+    while remaining > 0 {
+        let chunk = remaining.min(BUFFER_SIZE as u64) as usize;
+        let read = file
+            .read(&mut buffer[..chunk])
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        remaining -= read as u64;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+//残すファイル以外をごみ箱へ移すか、残すファイルへのハードリンクに置き換える
+pub async fn resolve(groups: Vec<DuplicateGroup>, action: DuplicateAction) -> Result<(), Error> {
+    for group in &groups {
+        let Some(keep) = group.files.get(group.keep) else {
+            continue;
+        };
+        //残すファイルが消えたり書き換えられたりしていたら、その組には手を付けない
+        if !unchanged(keep, group).await {
+            continue;
+        }
+        for path in group.removable() {
+            //検索後に変更されたファイルにも手を付けない
+            if !unchanged(path, group).await {
+                continue;
+            }
+            match action {
+                DuplicateAction::Trash => trash::delete(path).map_err(|e| Error::TrashFailed(e.to_string()))?,
+                DuplicateAction::HardLink => hard_link(keep, path).await?,
+            }
+        }
+    }
+    Ok(())
+}
+
+//検索したときと同じ大きさ・内容の通常ファイルのままか
+async fn unchanged(path: &Path, group: &DuplicateGroup) -> bool {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(meta) if meta.is_file() && meta.len() == group.size => {}
+        _ => return false,
+    }
+    hash_file(path, u64::MAX, &AtomicBool::new(false))
+        .await
+        .is_ok_and(|hash| hash == group.hash)
+}
+
+//一時名でリンクを作ってから置き換えるので、失敗しても元のファイルは残る
+async fn hard_link(original: &Path, path: &Path) -> Result<(), Error> {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let temporary = crate::file::unique_path(&path.with_file_name(format!(".{}.link", name)));
+    tokio::fs::hard_link(original, &temporary)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    if let Err(e) = tokio::fs::rename(&temporary, path).await {
+        let _ = tokio::fs::remove_file(&temporary).await;
+        return Err(Error::AsyncTokioIoError(e.kind()));
+    }
+    Ok(())
+}
@@ -1,15 +1,65 @@
-use std::fmt::{self,Display};
+use std::{fmt::{self,Display}, path::PathBuf};
+
+use crate::i18n::{tr, tr_fmt};
 
 
 #[derive(Debug,Clone)]
 pub enum Error {
     AsyncTokioIoError(tokio::io::ErrorKind),
+    InvalidFileName(String),
+    AlreadyExists(String),
+    InvalidDestination(PathBuf),
+    Cancelled,
+    InvalidPattern(String),
+    TrashFailed(String),
+    InvalidConfig(String),
+    ExportFailed(String),
+    ImportFailed(String),
+    DifferentRoots(PathBuf, PathBuf),
+    RemoveFailed(Vec<PathBuf>),
+    SourceNotRemoved(PathBuf),
+    ReplaceFailed(PathBuf),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Error::AsyncTokioIoError(e)=>write!(f,"{}",e),
+        match self {
+            Error::AsyncTokioIoError(kind)=>write!(f,"{}",io_error_text(*kind)),
+            Error::InvalidFileName(name)=>write!(f,"{}",tr_fmt("error.invalid_file_name",&[name])),
+            Error::AlreadyExists(name)=>write!(f,"{}",tr_fmt("error.already_exists",&[name])),
+            Error::InvalidDestination(path)=>write!(f,"{}",tr_fmt("error.invalid_destination",&[&path.display()])),
+            Error::Cancelled=>write!(f,"{}",tr("error.cancelled")),
+            Error::InvalidPattern(pattern)=>write!(f,"{}",tr_fmt("error.invalid_pattern",&[pattern])),
+            Error::TrashFailed(e)=>write!(f,"{}",tr_fmt("error.trash_failed",&[e])),
+            Error::InvalidConfig(e)=>write!(f,"{}",tr_fmt("error.invalid_config",&[e])),
+            Error::ExportFailed(e)=>write!(f,"{}",tr_fmt("error.export_failed",&[e])),
+            Error::ImportFailed(e)=>write!(f,"{}",tr_fmt("error.import_failed",&[e])),
+            Error::DifferentRoots(old,new)=>write!(f,"{}",tr_fmt("error.different_roots",&[&old.display(),&new.display()])),
+            Error::RemoveFailed(paths)=>{
+                let paths = paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join("\n");
+                write!(f,"{}",tr_fmt("error.remove_failed",&[&paths]))
+            }
+            Error::SourceNotRemoved(path)=>write!(f,"{}",tr_fmt("error.source_not_removed",&[&path.display()])),
+            Error::ReplaceFailed(path)=>write!(f,"{}",tr_fmt("error.replace_failed",&[&path.display()])),
         }
     }
-}
\ No newline at end of file
+}
+
+//よく出る種類だけ訳を用意し、それ以外は元の英語の説明を添える
+fn io_error_text(kind:tokio::io::ErrorKind)->String{
+    use tokio::io::ErrorKind;
+    let key = match kind {
+        ErrorKind::NotFound=>"error.io.not_found",
+        ErrorKind::PermissionDenied=>"error.io.permission_denied",
+        ErrorKind::AlreadyExists=>"error.io.already_exists",
+        ErrorKind::InvalidInput=>"error.io.invalid_input",
+        ErrorKind::InvalidData=>"error.io.invalid_data",
+        ErrorKind::TimedOut=>"error.io.timed_out",
+        ErrorKind::Interrupted=>"error.io.interrupted",
+        ErrorKind::Unsupported=>"error.io.unsupported",
+        ErrorKind::UnexpectedEof=>"error.io.unexpected_eof",
+        ErrorKind::OutOfMemory=>"error.io.out_of_memory",
+        _=>return tr_fmt("error.io",&[&kind]),
+    };
+    tr(key).to_string()
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::Display,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    scan::{permission_mode, ScanNode},
+};
+
+pub const SCHEMA: &str = "filersmanager-export/1";
+
+const HEADER: &[&str] = &["path", "name", "type", "bytes", "permissions", "modified", "accessed", "created"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: &'static [Self] = &[Self::Csv, Self::Tsv, Self::Json];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    //保存先の拡張子から形式を決める。判別できなければ選択中の形式を使う
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        Self::ALL.iter().copied().find(|format| format.extension() == ext)
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Tsv => "TSV",
+            ExportFormat::Json => "JSON",
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportEntry {
+    pub path: PathBuf,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub entry_type: &'static str,
+    pub bytes: u64,
+    pub permissions: String,
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+    pub created: Option<String>,
+}
+
+impl ExportEntry {
+    //フォルダのサイズは配下の合計なのでテーブルの値をそのまま使う
+    pub fn new(path: PathBuf, bytes: u64) -> Self {
+        let meta = fs::symlink_metadata(&path).ok();
+        let time = |f: fn(&Metadata) -> std::io::Result<SystemTime>| meta.as_ref().and_then(|meta| f(meta).ok()).map(timestamp);
+        Self {
+            name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            entry_type: match meta.as_ref().map(Metadata::file_type) {
+                Some(file_type) if file_type.is_dir() => "dir",
+                Some(file_type) if file_type.is_symlink() => "symlink",
+                Some(_) => "file",
+                None => "unknown",
+            },
+            bytes,
+            permissions: meta.as_ref().map(|meta| format!("{:04o}", permission_mode(meta))).unwrap_or_default(),
+            modified: time(Metadata::modified),
+            accessed: time(Metadata::accessed),
+            created: time(Metadata::created),
+            path,
+        }
+    }
+
+    //スナップショットなど手元にないファイルはスキャン時の情報から作る
+    pub fn from_node(path: PathBuf, node: &ScanNode) -> Self {
+        Self {
+            name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            entry_type: if node.is_dir { "dir" } else { "file" },
+            bytes: node.size,
+            permissions: format!("{:04o}", node.mode),
+            modified: node.modified.map(timestamp),
+            accessed: node.accessed.map(timestamp),
+            created: None,
+            path,
+        }
+    }
+}
+
+pub fn timestamp(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).to_rfc3339()
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    schema: &'static str,
+    exported_at: String,
+    entries: &'a [ExportEntry],
+}
+
+pub fn render(format: ExportFormat, entries: &[ExportEntry]) -> Result<String, Error> {
+    match format {
+        ExportFormat::Csv => Ok(render_csv(entries)),
+        ExportFormat::Tsv => Ok(render_tsv(entries)),
+        ExportFormat::Json => serde_json::to_string_pretty(&JsonExport {
+            schema: SCHEMA,
+            exported_at: timestamp(SystemTime::now()),
+            entries,
+        })
+        .map_err(|e| Error::ExportFailed(e.to_string())),
+    }
+}
+
+fn fields(entry: &ExportEntry) -> [String; 8] {
+    [
+        entry.path.display().to_string(),
+        entry.name.clone(),
+        entry.entry_type.to_string(),
+        entry.bytes.to_string(),
+        entry.permissions.clone(),
+        entry.modified.clone().unwrap_or_default(),
+        entry.accessed.clone().unwrap_or_default(),
+        entry.created.clone().unwrap_or_default(),
+    ]
+}
+
+fn render_csv(entries: &[ExportEntry]) -> String {
+    let mut lines = vec![HEADER.join(",")];
+    for entry in entries {
+        lines.push(fields(entry).iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+    }
+    //RFC 4180に合わせて改行はCRLFにする
+    lines.join("\r\n") + "\r\n"
+}
+
+//TSVには引用符の決まりがないのでタブと改行は空白に置き換える
+fn render_tsv(entries: &[ExportEntry]) -> String {
+    let mut lines = vec![HEADER.join("\t")];
+    for entry in entries {
+        lines.push(
+            fields(entry)
+                .iter()
+                .map(|field| field.replace(['\t', '\r', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t"),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,10 +1,23 @@
-use std::{path::{Path, PathBuf}};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{error::Error, Null};
+use async_recursion::async_recursion;
+
+use crate::{
+    error::Error,
+    export::{self, ExportEntry, ExportFormat},
+    i18n::{tr, tr_fmt},
+    ncdu,
+    scan::{ScanOptions, ScanResult},
+    snapshot,
+    Null,
+};
 
 pub async fn open_folder()->Option<PathBuf>{
     let picked_path = rfd::AsyncFileDialog::new()
-        .set_title("開きたいフォルダ")
+        .set_title(tr("dialog.open_folder"))
         .pick_folder()
         .await;
 
@@ -16,8 +29,39 @@ pub async fn open_folder()->Option<PathBuf>{
     }
 }
 
-pub async fn output_folder_infos(content:String)->Result<(),Error>{
+pub async fn pick_destination_folder()->Option<PathBuf>{
+    rfd::AsyncFileDialog::new()
+        .set_title(tr("dialog.destination"))
+        .pick_folder()
+        .await
+        .map(|picked_path| picked_path.path().into())
+}
+
+pub async fn output_folder_infos(entries:Vec<ExportEntry>,format:ExportFormat)->Result<(),Error>{
     let path = rfd::AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("filersmanager.{}", format.extension()))
+        .save_file()
+        .await
+        .as_ref()
+        .map(rfd::FileHandle::path)
+        .map(Path::to_owned);
+
+    if let Some(path) = path{
+        let format = ExportFormat::from_path(&path).unwrap_or(format);
+        tokio::fs::write(&path, export::render(format, &entries)?)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    }
+    Ok(())
+}
+
+//file_nameの拡張子で保存ダイアログの種類を絞る
+pub async fn save_as<C:AsRef<[u8]>>(content:C,file_name:&'static str)->Result<(),Error>{
+    let extension = Path::new(file_name).extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let path = rfd::AsyncFileDialog::new()
+        .add_filter(extension.to_uppercase(), &[extension])
+        .set_file_name(file_name)
         .save_file()
         .await
         .as_ref()
@@ -32,55 +76,219 @@ pub async fn output_folder_infos(content:String)->Result<(),Error>{
     Ok(())
 }
 
+pub async fn import_ncdu()->Result<(PathBuf,Arc<ScanResult>,ScanOptions),Error>{
+    let path = rfd::AsyncFileDialog::new()
+        .set_title(tr("dialog.ncdu"))
+        .add_filter("JSON", &["json"])
+        .pick_file()
+        .await
+        .ok_or(Error::Cancelled)?;
+    let text = tokio::fs::read_to_string(path.path())
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    ncdu::import(&text).map(|result| (path.path().to_owned(), Arc::new(result), ScanOptions::default()))
+}
+
+pub async fn open_snapshot()->Result<(PathBuf,Arc<ScanResult>,ScanOptions),Error>{
+    let path = rfd::AsyncFileDialog::new()
+        .set_title(tr("dialog.snapshot"))
+        .add_filter("GZ", &["gz"])
+        .pick_file()
+        .await
+        .ok_or(Error::Cancelled)?;
+    let bytes = tokio::fs::read(path.path())
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    snapshot::from_bytes(&bytes).map(|snapshot| (path.path().to_owned(), Arc::new(snapshot.result), snapshot.options))
+}
+
 pub async fn remove_file_dialog(path:PathBuf)->Result<(),Error>{
+    let description = tr_fmt("dialog.delete_confirm",&[&path.file_name().unwrap().to_string_lossy()]);
     let dialog_handle = rfd::AsyncMessageDialog::new()
         .set_buttons(rfd::MessageButtons::YesNoCancel)
-        .set_description(format!("{}を削除しますか",path.file_name().unwrap().to_str().unwrap()))
+        .set_description(description)
         .set_level(rfd::MessageLevel::Warning)
-        .set_title("ファイル削除")
+        .set_title(tr("dialog.delete_title"))
         .show()
         .await;
 
-    if dialog_handle == rfd::MessageDialogResult::Yes{
-        if path.exists(){
-            if path.is_file(){
-                tokio::fs::remove_file(path).await.map_err(|e| {
-                    Error::AsyncTokioIoError(e.kind())
-                })?;
-            }else if path.is_dir() {
-                tokio::fs::remove_dir_all(path).await.map_err(|e| {
-                    Error::AsyncTokioIoError(e.kind())
-                })?;
-            }
+    if dialog_handle == rfd::MessageDialogResult::Yes && path.exists(){
+        if path.is_file(){
+            tokio::fs::remove_file(path).await.map_err(|e| {
+                Error::AsyncTokioIoError(e.kind())
+            })?;
+        }else if path.is_dir() {
+            tokio::fs::remove_dir_all(path).await.map_err(|e| {
+                Error::AsyncTokioIoError(e.kind())
+            })?;
         }
     }
 
     Ok(())
 }
 
+pub fn validate_new_name(path:&Path,new_name:&str)->Result<PathBuf,Error>{
+    check_name(new_name)?;
+
+    //前後の空白は名前に含めない
+    let new_name = new_name.trim();
+    let new_path = path.with_file_name(new_name);
+    if new_path.exists() && !is_same_entry(path, &new_path){
+        return Err(Error::AlreadyExists(new_name.into()));
+    }
+    Ok(new_path)
+}
+
+pub fn validate_entry_name(dir:&Path,name:&str)->Result<PathBuf,Error>{
+    check_name(name)?;
+
+    let path = dir.join(name);
+    if std::fs::symlink_metadata(&path).is_ok(){
+        return Err(Error::AlreadyExists(name.into()));
+    }
+    Ok(path)
+}
+
+pub fn check_name(new_name:&str)->Result<(),Error>{
+    let trimmed = new_name.trim();
+    if trimmed.is_empty() || trimmed == "." || trimmed == ".."{
+        return Err(Error::InvalidFileName(new_name.into()));
+    }
+    //区切り文字やNULを含む名前は別ディレクトリを指してしまうので拒否
+    if new_name.chars().any(|c| std::path::is_separator(c) || c == '/' || c == '\\' || c == '\0'){
+        return Err(Error::InvalidFileName(new_name.into()));
+    }
+    #[cfg(windows)]
+    if new_name.chars().any(|c| matches!(c,'<'|'>'|':'|'"'|'|'|'?'|'*')) || new_name.ends_with(['.',' ']){
+        return Err(Error::InvalidFileName(new_name.into()));
+    }
+    Ok(())
+}
+
+//大文字小文字だけを変える名前変更は大文字小文字を区別しないFSでは同じエントリを指す
+#[cfg(unix)]
+pub fn is_same_entry(a:&Path,b:&Path)->bool{
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::symlink_metadata(a),std::fs::symlink_metadata(b)) {
+        (Ok(a),Ok(b))=>(a.dev(),a.ino()) == (b.dev(),b.ino()),
+        _=>false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn is_same_entry(a:&Path,b:&Path)->bool{
+    match (std::fs::canonicalize(a),std::fs::canonicalize(b)) {
+        (Ok(a),Ok(b))=>a == b,
+        _=>false,
+    }
+}
+
+//"name.ext"が既にあれば"name (1).ext"のように空いている名前を探す
+pub fn unique_path(path:&Path)->PathBuf{
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}",e.to_string_lossy())).unwrap_or_default();
+    let mut n = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{} ({}){}",stem,n,ext));
+        if std::fs::symlink_metadata(&candidate).is_err(){
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+pub async fn create_entry(path:PathBuf,entry_type:EntryType)->Result<PathBuf,Error>{
+    match entry_type {
+        EntryType::Dir=>tokio::fs::create_dir(&path).await,
+        EntryType::File=>tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .map(|_| ()),
+    }
+    .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    Ok(path)
+}
+
+pub async fn rename_entry(from:PathBuf,to:PathBuf)->Result<(PathBuf,PathBuf),Error>{
+    tokio::fs::rename(&from, &to)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    Ok((from,to))
+}
+
 pub async fn error_dialog_show(e:Error)->Null{
     let _ = rfd::AsyncMessageDialog::new()
         .set_level(rfd::MessageLevel::Error)
         .set_description(format!("{}",e))
-        .set_title("Error")
+        .set_title(tr("dialog.error"))
         .show()
         .await;
 
     Null{}
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
 pub enum EntryType {
     File,
     Dir,
 }
 
 impl EntryType {
-    pub fn generate(path:&PathBuf)->Self{
+    pub fn generate(path:&Path)->Self{
         if path.is_file(){
             Self::File
         }else{
             Self::Dir
         }
     }
-}
\ No newline at end of file
+}
+pub async fn remove_empty_dialog(dirs:Vec<PathBuf>,files:Vec<PathBuf>)->Result<(),Error>{
+    let description = tr_fmt("dialog.remove_empty_confirm",&[&dirs.len(),&files.len()]);
+    let dialog_handle = rfd::AsyncMessageDialog::new()
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .set_description(description)
+        .set_level(rfd::MessageLevel::Warning)
+        .set_title(tr("dialog.remove_empty_title"))
+        .show()
+        .await;
+
+    if dialog_handle == rfd::MessageDialogResult::Yes{
+        remove_empty_entries(dirs, files).await?;
+    }
+    Ok(())
+}
+
+//スキャン後に中身が増えたものは消さないよう、空のままのものだけを削除する
+//削除できないものがあっても残りは続け、最後にまとめて報告する
+pub async fn remove_empty_entries(dirs:Vec<PathBuf>,files:Vec<PathBuf>)->Result<(),Error>{
+    let mut failed = vec![];
+    for path in files{
+        let empty = tokio::fs::symlink_metadata(&path).await.is_ok_and(|meta| meta.is_file() && meta.len() == 0);
+        if empty && tokio::fs::remove_file(&path).await.is_err(){
+            failed.push(path);
+        }
+    }
+    for path in dirs{
+        if remove_empty_dir(&path).await.is_err(){
+            failed.push(path);
+        }
+    }
+    if failed.is_empty(){
+        Ok(())
+    }else{
+        Err(Error::RemoveFailed(failed))
+    }
+}
+
+#[async_recursion]
+async fn remove_empty_dir(path:&Path)->Result<(),Error>{
+    let mut entries = tokio::fs::read_dir(path).await.map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    while let Ok(Some(entry)) = entries.next_entry().await{
+        if entry.file_type().await.is_ok_and(|file_type| file_type.is_dir()){
+            remove_empty_dir(&entry.path()).await?;
+        }
+    }
+    tokio::fs::remove_dir(path).await.map_err(|e| Error::AsyncTokioIoError(e.kind()))
+}
@@ -0,0 +1,99 @@
+use std::{fmt::Display, path::Path, sync::RwLock};
+
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+//設定ファイルで上書きされた分類ごとの色
+static COLORS: RwLock<Vec<(FileType, Color)>> = RwLock::new(Vec::new());
+
+pub fn set_colors(colors: Vec<(FileType, Color)>) {
+    if let Ok(mut current) = COLORS.write() {
+        *current = colors;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    Video,
+    Image,
+    Audio,
+    Archive,
+    Source,
+    Binary,
+    Document,
+    Other,
+}
+
+impl FileType {
+    pub const ALL: &'static [Self] = &[
+        Self::Video,
+        Self::Image,
+        Self::Audio,
+        Self::Archive,
+        Self::Source,
+        Self::Binary,
+        Self::Document,
+        Self::Other,
+    ];
+
+    pub fn from_name(name: &str) -> Self {
+        let ext = Path::new(name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        Self::from_extension(&ext)
+    }
+
+    pub fn from_extension(ext: &str) -> Self {
+        match ext {
+            "mp4" | "mkv" | "avi" | "mov" | "wmv" | "webm" | "flv" | "m4v" | "mpg" | "mpeg" | "m2ts" => Self::Video,
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "svg" | "ico" | "heic" | "raw" | "cr2" | "nef" | "psd" => Self::Image,
+            "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" => Self::Audio,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "lz4" | "iso" | "dmg" | "cab" => Self::Archive,
+            "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "cs" | "java" | "kt" | "py" | "js" | "ts" | "jsx" | "tsx" | "go" | "rb" | "php" | "swift" | "sh" | "ps1" | "html" | "css" | "toml" | "json" | "yaml" | "yml" | "xml" | "sql" => Self::Source,
+            "exe" | "dll" | "so" | "dylib" | "a" | "lib" | "o" | "obj" | "rlib" | "bin" | "class" | "jar" | "pyc" | "wasm" | "msi" | "deb" | "rpm" | "apk" => Self::Binary,
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp" | "txt" | "md" | "rtf" | "csv" | "epub" => Self::Document,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        COLORS
+            .read()
+            .ok()
+            .and_then(|colors| colors.iter().find(|(file_type, _)| file_type == self).map(|(_, color)| *color))
+            .unwrap_or_else(|| self.default_color())
+    }
+
+    pub fn default_color(&self) -> Color {
+        match self {
+            FileType::Video => Color::from_rgb8(0xe0, 0x6c, 0x75),
+            FileType::Image => Color::from_rgb8(0x98, 0xc3, 0x79),
+            FileType::Audio => Color::from_rgb8(0xc6, 0x78, 0xdd),
+            FileType::Archive => Color::from_rgb8(0xe5, 0xc0, 0x7b),
+            FileType::Source => Color::from_rgb8(0x61, 0xaf, 0xef),
+            FileType::Binary => Color::from_rgb8(0xd1, 0x9a, 0x66),
+            FileType::Document => Color::from_rgb8(0x56, 0xb6, 0xc2),
+            FileType::Other => Color::from_rgb8(0x7f, 0x84, 0x8e),
+        }
+    }
+}
+
+impl Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileType::Video => tr("filetype.video"),
+            FileType::Image => tr("filetype.image"),
+            FileType::Audio => tr("filetype.audio"),
+            FileType::Archive => tr("filetype.archive"),
+            FileType::Source => tr("filetype.source"),
+            FileType::Binary => tr("filetype.binary"),
+            FileType::Document => tr("filetype.document"),
+            FileType::Other => tr("filetype.other"),
+        }
+        .fmt(f)
+    }
+}
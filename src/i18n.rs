@@ -0,0 +1,483 @@
+use std::{
+    env,
+    fmt::{Display, Write},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Language {
+    pub const ALL: &'static [Self] = &[Self::En, Self::Ja];
+
+    //LC_ALL、LC_MESSAGES、LANG の順に最初に設定されているものを使い、どれもなければOSの設定を見る
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .or_else(os_locale)
+            .map(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    //"ja_JP.UTF-8" のようなロケール名から選ぶ。対応していない言語は英語にする
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.to_lowercase().starts_with("ja") {
+            Self::Ja
+        } else {
+            Self::En
+        }
+    }
+
+    pub fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::En => EN,
+            Language::Ja => JA,
+        }
+    }
+
+    //訳がなければ英語、英語にもなければキーをそのまま返す
+    pub fn tr(&self, key: &'static str) -> &'static str {
+        let find = |catalog: &'static [(&'static str, &'static str)]| {
+            catalog.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+        };
+        find(self.catalog()).or_else(|| find(EN)).unwrap_or(key)
+    }
+
+    //訳文の "{}" を順に args で置き換える
+    pub fn tr_fmt(&self, key: &'static str, args: &[&dyn Display]) -> String {
+        let mut text = String::new();
+        let mut args = args.iter();
+        let mut rest = self.tr(key);
+        while let Some(index) = rest.find("{}") {
+            text.push_str(&rest[..index]);
+            if let Some(arg) = args.next() {
+                let _ = write!(text, "{}", arg);
+            }
+            rest = &rest[index + 2..];
+        }
+        text.push_str(rest);
+        text
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::En => "English",
+            Language::Ja => "日本語",
+        }
+        .fmt(f)
+    }
+}
+
+//Windowsは環境変数にロケールを持たないので、ユーザーの既定ロケール名("ja-JP" など)を取得する
+#[cfg(windows)]
+fn os_locale() -> Option<String> {
+    const LOCALE_NAME_MAX_LENGTH: usize = 85;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetUserDefaultLocaleName(locale_name: *mut u16, length: i32) -> i32;
+    }
+    let mut buffer = [0u16; LOCALE_NAME_MAX_LENGTH];
+    let length = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    //戻り値は終端のNULを含む文字数で、失敗したときは0
+    if length <= 1 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&buffer[..length as usize - 1]))
+}
+
+//Finderから起動したアプリには LANG が渡らないので、システム設定の AppleLocale を読む
+#[cfg(target_os = "macos")]
+fn os_locale() -> Option<String> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleLocale"])
+        .output()
+        .ok()?;
+    let locale = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !locale.is_empty()).then_some(locale)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn os_locale() -> Option<String> {
+    None
+}
+
+//表示言語はビューやダイアログ、エラーメッセージのどこからでも参照するのでプロセス全体で共有する
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    let value = match language {
+        Language::En => 0,
+        Language::Ja => 1,
+    };
+    LANGUAGE.store(value, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::Ja,
+        _ => Language::En,
+    }
+}
+
+pub fn tr(key: &'static str) -> &'static str {
+    language().tr(key)
+}
+
+pub fn tr_fmt(key: &'static str, args: &[&dyn Display]) -> String {
+    language().tr_fmt(key, args)
+}
+
+const EN: &[(&str, &str)] = &[
+    ("path.placeholder", "Enter a folder path"),
+    ("toolbar.size_bars", "size bars"),
+    ("toolbar.largest", "Largest"),
+    ("toolbar.new_folder", "New Folder"),
+    ("toolbar.new_file", "New File"),
+    ("toolbar.batch_rename", "Batch Rename"),
+    ("toolbar.shred", "Shred"),
+    ("toolbar.copy", "Copy"),
+    ("toolbar.move", "Move"),
+    ("toolbar.report", "Report"),
+    ("toolbar.save_snapshot", "Save Snapshot"),
+    ("toolbar.open_snapshot", "Open Snapshot"),
+    ("toolbar.diff", "Diff"),
+    ("tooltip.open_folder", "Choose a folder to open"),
+    ("tooltip.largest", "Show the largest files in the whole scanned folder"),
+    ("tooltip.new_folder", "Create a new folder"),
+    ("tooltip.new_file", "Create a new file"),
+    ("tooltip.batch_rename", "Rename the selected items at once"),
+    ("tooltip.shred", "Overwrite the selected items, then delete them"),
+    ("tooltip.copy", "Copy the selected items"),
+    ("tooltip.move", "Move the selected items"),
+    ("tooltip.output", "Export the table"),
+    ("tooltip.report", "Export an HTML report"),
+    ("tooltip.ncdu_import", "Open an ncdu export file"),
+    ("tooltip.ncdu_export", "Export the scan in ncdu format"),
+    ("tooltip.save_snapshot", "Save the scan as a snapshot"),
+    ("tooltip.open_snapshot", "Open a saved snapshot"),
+    ("tooltip.diff", "Compare a snapshot with the current scan"),
+    ("snapshot.banner", "Viewing a snapshot (read-only): {}  {}"),
+    ("snapshot.exclude", "Excluded when scanned: {}"),
+    ("snapshot.one_file_system", "Scanned without crossing mount points"),
+    ("diff.hint", "Choose a snapshot to compare with the Diff button"),
+    ("diff.header", "{} ({}) → now ({})  {} → {}  {}  files {}"),
+    ("view.table", "Table"),
+    ("view.treemap", "Treemap"),
+    ("view.sunburst", "Sunburst"),
+    ("view.types", "File Types"),
+    ("view.age", "Age"),
+    ("view.duplicates", "Duplicates"),
+    ("view.empty", "Empty"),
+    ("view.junk", "Reclaimable"),
+    ("view.volumes", "Volumes"),
+    ("view.diff", "Diff"),
+    ("color.file_type", "File Type"),
+    ("color.age", "Age"),
+    ("units.iec", "KiB/MiB"),
+    ("units.si", "kB/MB"),
+    ("units.bytes", "bytes"),
+    ("sort.size", "sort: size"),
+    ("sort.name", "sort: name"),
+    ("sort.modified", "sort: modified"),
+    ("case.keep", "Keep"),
+    ("case.lower", "lower"),
+    ("case.upper", "UPPER"),
+    ("case.title", "Title"),
+    ("duplicates.trash", "move to trash"),
+    ("duplicates.hard_link", "replace with hard link"),
+    ("age.day", "Last day"),
+    ("age.week", "Last week"),
+    ("age.month", "Last month"),
+    ("age.year", "Last year"),
+    ("age.older", "Older"),
+    ("age.unknown", "Unknown"),
+    ("filetype.video", "Video"),
+    ("filetype.image", "Images"),
+    ("filetype.audio", "Audio"),
+    ("filetype.archive", "Archives"),
+    ("filetype.source", "Source code"),
+    ("filetype.binary", "Binaries"),
+    ("filetype.document", "Documents"),
+    ("filetype.other", "Other"),
+    ("diff.added", "new"),
+    ("diff.removed", "deleted"),
+    ("diff.changed", "changed"),
+    ("filter.no_extension", "(no extension)"),
+    ("filter.stale", "not modified for {} days, {} or larger"),
+    ("filter.largest", "largest {} files"),
+    ("footer.total", "total:{}"),
+    ("footer.filter", "filter:{}"),
+    ("button.show", "show"),
+    ("button.search", "search"),
+    ("button.search_again", "search again"),
+    ("button.cancel", "cancel"),
+    ("button.apply", "apply"),
+    ("button.close", "close"),
+    ("button.clear", "clear"),
+    ("button.create", "create"),
+    ("button.refresh", "refresh"),
+    ("button.remove_all", "remove all"),
+    ("button.rename", "rename"),
+    ("button.delete", "delete"),
+    ("button.skip", "skip"),
+    ("button.overwrite", "overwrite"),
+    ("button.shred", "shred"),
+    ("types.category", "Category"),
+    ("types.extension", "Extension"),
+    ("types.row", "{}  {} files  {}  {}"),
+    ("age.row", "{} files  {}  {}"),
+    ("age.stale_prefix", "stale files: not modified for"),
+    ("age.stale_days", "days,"),
+    ("age.stale_size", "or larger"),
+    ("duplicates.hint", "Find files with identical contents in the whole scanned folder"),
+    ("duplicates.group", "{} × {} files  wasted: {}"),
+    ("duplicates.summary", "{} groups  wasted: {}"),
+    ("duplicates.action", "For all but the kept file:"),
+    ("empty.none", "There are no empty folders or zero-byte files"),
+    ("empty.summary", "{} empty folders, {} zero-byte files"),
+    ("empty.folders", "Empty folders"),
+    ("empty.files", "Zero-byte files"),
+    ("junk.count", "{} items  {}"),
+    ("junk.total", "Regenerable data: {}"),
+    ("junk.custom_rules", "Add your own rules as [[rule]] in {}"),
+    ("volumes.usage", "{} / {}  free: {}"),
+    ("volumes.hint", "Click a volume to scan it"),
+    ("new_entry.folder", "New folder name"),
+    ("new_entry.file", "New file name"),
+    ("rename.find", "find"),
+    ("rename.replace", "replace"),
+    ("rename.regex", "regex"),
+    ("rename.unchanged", "(unchanged)"),
+    ("rename.invalid", "(invalid name)"),
+    ("rename.collision", "(name collision)"),
+    ("shred.progress", "Overwriting {}/{}"),
+    ("shred.confirm", "{} items will be overwritten and deleted. This cannot be undone. If cancelled, files already being overwritten are still deleted."),
+    ("shred.warning", "Overwriting has no effect on copy-on-write file systems (Btrfs, ZFS, APFS), SSDs, or with journaling and snapshots, because the original data remains"),
+    ("shred.passes", "passes"),
+    ("transfer.copying", "Copying"),
+    ("transfer.moving", "Moving"),
+    ("transfer.exists", "{} already exists"),
+    ("transfer.apply_to_all", "apply to all"),
+    ("column.index", "Index"),
+    ("column.name", "Name"),
+    ("column.size", "Size"),
+    ("column.modified", "Modified Time"),
+    ("column.rename", "Rename"),
+    ("column.delete", "Delete"),
+    ("dialog.open_folder", "Folder to open"),
+    ("dialog.destination", "Destination folder"),
+    ("dialog.ncdu", "ncdu export file"),
+    ("dialog.snapshot", "Snapshot"),
+    ("dialog.delete_title", "Delete file"),
+    ("dialog.delete_confirm", "Delete {}?"),
+    ("dialog.remove_empty_title", "Remove empty items"),
+    ("dialog.remove_empty_confirm", "Remove {} empty folders and {} empty files?"),
+    ("dialog.error", "Error"),
+    ("error.invalid_file_name", "invalid file name: {}"),
+    ("error.already_exists", "{} already exists"),
+    ("error.invalid_destination", "cannot transfer into {}"),
+    ("error.cancelled", "cancelled"),
+    ("error.invalid_pattern", "invalid pattern: {}"),
+    ("error.trash_failed", "failed to move to trash: {}"),
+    ("error.invalid_config", "invalid config: {}"),
+    ("error.export_failed", "failed to export: {}"),
+    ("error.import_failed", "failed to import: {}"),
+    ("error.different_roots", "cannot compare scans of different folders: {} and {}"),
+    ("error.remove_failed", "failed to remove:\n{}"),
+    ("error.source_not_removed", "moved, but the original could not be fully removed: {}"),
+    ("error.replace_failed", "could not replace the existing item; the transferred data was kept at {}"),
+    ("error.io", "I/O error: {}"),
+    ("error.io.not_found", "not found"),
+    ("error.io.permission_denied", "permission denied"),
+    ("error.io.already_exists", "already exists"),
+    ("error.io.invalid_input", "invalid input"),
+    ("error.io.invalid_data", "invalid data"),
+    ("error.io.timed_out", "timed out"),
+    ("error.io.interrupted", "interrupted"),
+    ("error.io.unsupported", "not supported"),
+    ("error.io.unexpected_eof", "unexpected end of file"),
+    ("error.io.out_of_memory", "out of memory"),
+];
+
+const JA: &[(&str, &str)] = &[
+    ("path.placeholder", "フォルダのパスを入力"),
+    ("toolbar.size_bars", "サイズバー"),
+    ("toolbar.largest", "大きいファイル"),
+    ("toolbar.new_folder", "新規フォルダ"),
+    ("toolbar.new_file", "新規ファイル"),
+    ("toolbar.batch_rename", "一括リネーム"),
+    ("toolbar.shred", "完全削除"),
+    ("toolbar.copy", "コピー"),
+    ("toolbar.move", "移動"),
+    ("toolbar.report", "レポート"),
+    ("toolbar.save_snapshot", "スナップショット保存"),
+    ("toolbar.open_snapshot", "スナップショットを開く"),
+    ("toolbar.diff", "比較"),
+    ("tooltip.open_folder", "開きたいフォルダを選択"),
+    ("tooltip.largest", "スキャンしたフォルダ全体から大きいファイルを表示"),
+    ("tooltip.new_folder", "新しいフォルダを作成"),
+    ("tooltip.new_file", "新しいファイルを作成"),
+    ("tooltip.batch_rename", "選択項目の名前を一括変更"),
+    ("tooltip.shred", "選択項目を上書きしてから削除"),
+    ("tooltip.copy", "選択項目をコピー"),
+    ("tooltip.move", "選択項目を移動"),
+    ("tooltip.output", "出力"),
+    ("tooltip.report", "HTMLレポートを出力"),
+    ("tooltip.ncdu_import", "ncduのエクスポートファイルを読み込む"),
+    ("tooltip.ncdu_export", "スキャン結果をncdu形式で出力"),
+    ("tooltip.save_snapshot", "スキャン結果をスナップショットとして保存"),
+    ("tooltip.open_snapshot", "保存したスナップショットを開く"),
+    ("tooltip.diff", "スナップショットと表示中のスキャン結果を比較"),
+    ("snapshot.banner", "スナップショットを表示中(読み取り専用): {}  {}"),
+    ("snapshot.exclude", "スキャン時の除外: {}"),
+    ("snapshot.one_file_system", "マウントポイントを越えずにスキャン"),
+    ("diff.hint", "比較ボタンで比較するスナップショットを選んでください"),
+    ("diff.header", "{} ({}) → 現在 ({})  {} → {}  {}  ファイル数 {}"),
+    ("view.table", "テーブル"),
+    ("view.treemap", "ツリーマップ"),
+    ("view.sunburst", "サンバースト"),
+    ("view.types", "種類"),
+    ("view.age", "経過日数"),
+    ("view.duplicates", "重複"),
+    ("view.empty", "空"),
+    ("view.junk", "削除可能"),
+    ("view.volumes", "ボリューム"),
+    ("view.diff", "比較"),
+    ("color.file_type", "種類"),
+    ("color.age", "経過日数"),
+    ("units.iec", "KiB/MiB"),
+    ("units.si", "kB/MB"),
+    ("units.bytes", "バイト"),
+    ("sort.size", "並び順: サイズ"),
+    ("sort.name", "並び順: 名前"),
+    ("sort.modified", "並び順: 更新日時"),
+    ("case.keep", "そのまま"),
+    ("case.lower", "小文字"),
+    ("case.upper", "大文字"),
+    ("case.title", "先頭を大文字"),
+    ("duplicates.trash", "ゴミ箱に移動"),
+    ("duplicates.hard_link", "ハードリンクに置き換え"),
+    ("age.day", "1日以内"),
+    ("age.week", "1週間以内"),
+    ("age.month", "1か月以内"),
+    ("age.year", "1年以内"),
+    ("age.older", "1年より前"),
+    ("age.unknown", "不明"),
+    ("filetype.video", "動画"),
+    ("filetype.image", "画像"),
+    ("filetype.audio", "音声"),
+    ("filetype.archive", "アーカイブ"),
+    ("filetype.source", "ソースコード"),
+    ("filetype.binary", "実行ファイル"),
+    ("filetype.document", "文書"),
+    ("filetype.other", "その他"),
+    ("diff.added", "新規"),
+    ("diff.removed", "削除"),
+    ("diff.changed", "変更"),
+    ("filter.no_extension", "(拡張子なし)"),
+    ("filter.stale", "{}日以上更新なし、{}以上"),
+    ("filter.largest", "大きい順に{}件"),
+    ("footer.total", "合計:{}"),
+    ("footer.filter", "絞り込み:{}"),
+    ("button.show", "表示"),
+    ("button.search", "検索"),
+    ("button.search_again", "再検索"),
+    ("button.cancel", "キャンセル"),
+    ("button.apply", "実行"),
+    ("button.close", "閉じる"),
+    ("button.clear", "解除"),
+    ("button.create", "作成"),
+    ("button.refresh", "更新"),
+    ("button.remove_all", "すべて削除"),
+    ("button.rename", "名前変更"),
+    ("button.delete", "削除"),
+    ("button.skip", "スキップ"),
+    ("button.overwrite", "上書き"),
+    ("button.shred", "完全削除"),
+    ("types.category", "分類"),
+    ("types.extension", "拡張子"),
+    ("types.row", "{}  {}件  {}  {}"),
+    ("age.row", "{}件  {}  {}"),
+    ("age.stale_prefix", "更新されていないファイル:"),
+    ("age.stale_days", "日以上、"),
+    ("age.stale_size", "以上"),
+    ("duplicates.hint", "スキャンしたフォルダ全体から内容が同じファイルを探します"),
+    ("duplicates.group", "{} × {}件  無駄:{}"),
+    ("duplicates.summary", "{}組  無駄:{}"),
+    ("duplicates.action", "残す以外を"),
+    ("empty.none", "空のフォルダや0バイトのファイルはありません"),
+    ("empty.summary", "空のフォルダ{}件、0バイトのファイル{}件"),
+    ("empty.folders", "空のフォルダ"),
+    ("empty.files", "0バイトのファイル"),
+    ("junk.count", "{}件  {}"),
+    ("junk.total", "再生成できるデータ: {}"),
+    ("junk.custom_rules", "独自のルールは {} に [[rule]] として追加できます"),
+    ("volumes.usage", "{} / {}  空き:{}"),
+    ("volumes.hint", "クリックしたボリュームをスキャンします"),
+    ("new_entry.folder", "新しいフォルダ名"),
+    ("new_entry.file", "新しいファイル名"),
+    ("rename.find", "検索"),
+    ("rename.replace", "置換"),
+    ("rename.regex", "正規表現"),
+    ("rename.unchanged", "(変更なし)"),
+    ("rename.invalid", "(無効な名前)"),
+    ("rename.collision", "(名前が衝突)"),
+    ("shred.progress", "上書き中 {}/{}"),
+    ("shred.confirm", "{}項目を上書きしてから削除します。元に戻せません。中断した場合も上書きを始めたファイルは削除されます。"),
+    ("shred.warning", "上書き削除はコピーオンライト(Btrfs, ZFS, APFS)やSSD、ジャーナリング・スナップショットのある環境では元のデータが残るため効果がありません"),
+    ("shred.passes", "上書き回数"),
+    ("transfer.copying", "コピー中"),
+    ("transfer.moving", "移動中"),
+    ("transfer.exists", "{}は既に存在します"),
+    ("transfer.apply_to_all", "すべてに適用"),
+    ("column.index", "番号"),
+    ("column.name", "名前"),
+    ("column.size", "サイズ"),
+    ("column.modified", "更新日時"),
+    ("column.rename", "名前変更"),
+    ("column.delete", "削除"),
+    ("dialog.open_folder", "開きたいフォルダ"),
+    ("dialog.destination", "転送先フォルダ"),
+    ("dialog.ncdu", "ncduのエクスポートファイル"),
+    ("dialog.snapshot", "スナップショット"),
+    ("dialog.delete_title", "ファイル削除"),
+    ("dialog.delete_confirm", "{}を削除しますか"),
+    ("dialog.remove_empty_title", "空の項目を削除"),
+    ("dialog.remove_empty_confirm", "空のフォルダ{}個と空のファイル{}個を削除しますか"),
+    ("dialog.error", "エラー"),
+    ("error.invalid_file_name", "無効なファイル名です: {}"),
+    ("error.already_exists", "{}は既に存在します"),
+    ("error.invalid_destination", "{}には転送できません"),
+    ("error.cancelled", "キャンセルしました"),
+    ("error.invalid_pattern", "無効なパターンです: {}"),
+    ("error.trash_failed", "ゴミ箱に移動できませんでした: {}"),
+    ("error.invalid_config", "設定ファイルが不正です: {}"),
+    ("error.export_failed", "出力に失敗しました: {}"),
+    ("error.import_failed", "読み込みに失敗しました: {}"),
+    ("error.different_roots", "異なるフォルダのスキャン結果は比較できません: {} と {}"),
+    ("error.remove_failed", "削除できませんでした:\n{}"),
+    ("error.source_not_removed", "移動しましたが、移動元を削除しきれませんでした: {}"),
+    ("error.replace_failed", "既存の項目を置き換えられませんでした。転送した内容は {} に残っています"),
+    ("error.io", "入出力エラー: {}"),
+    ("error.io.not_found", "見つかりません"),
+    ("error.io.permission_denied", "アクセスが拒否されました"),
+    ("error.io.already_exists", "既に存在します"),
+    ("error.io.invalid_input", "入力が不正です"),
+    ("error.io.invalid_data", "データが不正です"),
+    ("error.io.timed_out", "タイムアウトしました"),
+    ("error.io.interrupted", "中断されました"),
+    ("error.io.unsupported", "サポートされていません"),
+    ("error.io.unexpected_eof", "ファイルが途中で終わっています"),
+    ("error.io.out_of_memory", "メモリが不足しています"),
+];
@@ -0,0 +1,197 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, scan::ScanNode};
+
+//名前に対する正規表現で再生成できるフォルダやファイルを見分ける
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JunkRule {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub dir: bool,
+    //同じフォルダにこのいずれかがある場合だけ一致とみなす(例: target と Cargo.toml)
+    #[serde(default)]
+    pub siblings: Vec<String>,
+}
+
+impl JunkRule {
+    fn new(name: &str, pattern: &str, dir: bool, siblings: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            pattern: pattern.into(),
+            dir,
+            siblings: siblings.iter().map(|sibling| sibling.to_string()).collect(),
+        }
+    }
+
+    pub fn compile(&self) -> Result<Regex, Error> {
+        Regex::new(&self.pattern).map_err(|_| Error::InvalidPattern(self.pattern.clone()))
+    }
+}
+
+pub fn builtin_rules() -> Vec<JunkRule> {
+    vec![
+        JunkRule::new("Rust target", "^target$", true, &["Cargo.toml"]),
+        JunkRule::new("node_modules", "^node_modules$", true, &[]),
+        JunkRule::new("Python cache", r"^(__pycache__|\.pytest_cache|\.mypy_cache|\.tox)$", true, &[]),
+        JunkRule::new("Gradle", r"^\.gradle$", true, &[]),
+        JunkRule::new("Cache folders", r"^\.cache$", true, &[]),
+        JunkRule::new(
+            "Build output",
+            "^(build|dist|out)$",
+            true,
+            &["package.json", "CMakeLists.txt", "build.gradle", "build.gradle.kts", "pyproject.toml", "setup.py"],
+        ),
+        JunkRule::new("Rotated logs", r"(?i)\.log\.(\d+|\d+\.gz|gz|old)$", false, &[]),
+    ]
+}
+
+//組み込みルールの無効化と利用者が追加したルールを保存する
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JunkConfig {
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<JunkRule>,
+}
+
+impl JunkConfig {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("filersmanager").join("junk.toml"))
+    }
+
+    //設定ファイルがない、または読めない場合は組み込みルールだけを使う
+    pub fn load() -> Self {
+        let Some(text) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Self::default();
+        };
+        Self::parse(&text).unwrap_or_else(|e| {
+            log::error!("{}", e);
+            Self::default()
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(|e| Error::InvalidConfig(e.to_string()))
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let text = toml::to_string(self).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        }
+        fs::write(path, text).map_err(|e| Error::AsyncTokioIoError(e.kind()))
+    }
+
+    pub fn all_rules(&self) -> Vec<JunkRule> {
+        builtin_rules().into_iter().chain(self.rules.iter().cloned()).collect()
+    }
+
+    pub fn is_enabled(&self, rule: &JunkRule) -> bool {
+        !self.disabled.contains(&rule.name)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        self.disabled.retain(|disabled| disabled != name);
+        if !enabled {
+            self.disabled.push(name.into());
+        }
+    }
+
+    pub fn enabled_rules(&self) -> Vec<JunkRule> {
+        self.all_rules().into_iter().filter(|rule| self.is_enabled(rule)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JunkMatch {
+    pub rule: usize,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub is_dir: bool,
+}
+
+//一致したフォルダの中はそれ以上調べない。正規表現が不正なルールは無視する
+pub fn detect(tree: &ScanNode, root: &Path, rules: &[JunkRule]) -> Vec<JunkMatch> {
+    let compiled: Vec<(usize, &JunkRule, Regex)> = rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| rule.compile().ok().map(|regex| (index, rule, regex)))
+        .collect();
+    let mut matches = vec![];
+    detect_in(tree, root, &compiled, &mut matches);
+    matches
+}
+
+fn detect_in(node: &ScanNode, path: &Path, rules: &[(usize, &JunkRule, Regex)], matches: &mut Vec<JunkMatch>) {
+    for child in &node.children {
+        let child_path = path.join(&child.name);
+        let rule = rules.iter().find(|(_, rule, regex)| {
+            rule.dir == child.is_dir
+                && regex.is_match(&child.name)
+                && (rule.siblings.is_empty()
+                    || node.children.iter().any(|sibling| rule.siblings.contains(&sibling.name)))
+        });
+        match rule {
+            Some((index, _, _)) => matches.push(JunkMatch {
+                rule: *index,
+                path: child_path,
+                size: child.size,
+                modified: child.modified,
+                is_dir: child.is_dir,
+            }),
+            None if child.is_dir => detect_in(child, &child_path, rules, matches),
+            None => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JunkSummary {
+    pub rule: JunkRule,
+    pub enabled: bool,
+    pub count: u64,
+    pub bytes: u64,
+    pub error: Option<Error>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JunkReport {
+    pub total: u64,
+    pub rules: Vec<JunkSummary>,
+}
+
+pub fn report(tree: &ScanNode, root: &Path, config: &JunkConfig) -> JunkReport {
+    let enabled = config.enabled_rules();
+    let matches = detect(tree, root, &enabled);
+    let rules = config
+        .all_rules()
+        .into_iter()
+        .map(|rule| {
+            let index = enabled.iter().position(|enabled| *enabled == rule);
+            let found = matches.iter().filter(|found| Some(found.rule) == index);
+            JunkSummary {
+                enabled: index.is_some(),
+                count: found.clone().count() as u64,
+                bytes: found.map(|found| found.size).sum(),
+                error: rule.compile().err(),
+                rule,
+            }
+        })
+        .collect();
+    JunkReport {
+        total: matches.iter().map(|found| found.size).sum(),
+        rules,
+    }
+}
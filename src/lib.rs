@@ -1,12 +1,43 @@
-use std::{fs::Permissions, path::PathBuf, time::SystemTime};
+use std::{path::PathBuf, sync::Arc};
 
-use iced::widget::{scrollable, text_editor};
-use widget::{Category, FileTableRow};
+use analysis::{MinSize, TypeFilter};
+use batch_rename::CaseConversion;
+use cli::SortKey;
+use duplicate::{DuplicateAction, DuplicateGroup, DuplicateProgress};
+use export::ExportFormat;
+use i18n::Language;
+use iced::widget::scrollable;
+use scan::{ScanOptions, ScanResult, TableEntry};
+use shred::ShredProgress;
+use theme::ThemeChoice;
+use transfer::{ConflictAction, TransferEvent, TransferKind};
+use units::UnitStyle;
+use volume::Volume;
+use widget::{treemap::ColorMode, FileTableRow, ViewMode};
 
 pub mod icon;
 pub mod file;
 pub mod error;
 pub mod widget;
+pub mod transfer;
+pub mod batch_rename;
+pub mod shred;
+pub mod scan;
+pub mod filetype;
+pub mod analysis;
+pub mod duplicate;
+pub mod junk;
+pub mod volume;
+pub mod export;
+pub mod report;
+pub mod ncdu;
+pub mod snapshot;
+pub mod diff;
+pub mod cli;
+pub mod units;
+pub mod settings;
+pub mod theme;
+pub mod i18n;
 
 
 #[derive(Debug,Clone)]
@@ -19,7 +50,8 @@ pub enum Message {
     FolderOpened(Option<PathBuf>),
     OutputFileInfos,
     FileSearch,
-    FileSerachedConvert(Vec<(PathBuf,u64,Permissions,Option<SystemTime>)>),
+    FileSerachedConvert(Vec<TableEntry>),
+    Scanned(Arc<ScanResult>),
     FileSeached((Vec<FileTableRow>,String)),
     EventOccured(iced::event::Event),
     ErrorDialogShow(Result<(),error::Error>),
@@ -28,4 +60,79 @@ pub enum Message {
     Resizing(usize,f32),
     Resized,
     Delete(usize),
+    Deleted(PathBuf,Result<(),error::Error>),
+    Select(usize,bool),
+    RenameStart(usize),
+    RenameInput(String),
+    RenameSubmit,
+    RenameCancel,
+    Renamed(Result<(PathBuf,PathBuf),error::Error>),
+    TransferStart(TransferKind),
+    TransferDestinationPicked(TransferKind,Option<PathBuf>),
+    TransferEvent(TransferEvent),
+    TransferConflictResolved(ConflictAction),
+    TransferApplyToAll(bool),
+    TransferCancel,
+    TransferFinished(Result<(),error::Error>),
+    NewEntryStart(file::EntryType),
+    NewEntryInput(String),
+    NewEntrySubmit,
+    NewEntryCancel,
+    NewEntryCreated(Result<PathBuf,error::Error>),
+    BatchRenameOpen,
+    BatchRenameFind(String),
+    BatchRenameReplace(String),
+    BatchRenameRegex(bool),
+    BatchRenameTemplate(String),
+    BatchRenameCase(CaseConversion),
+    BatchRenameApply,
+    BatchRenameCancel,
+    BatchRenamed(Result<Vec<(PathBuf,PathBuf)>,error::Error>),
+    ShredOpen,
+    ShredPasses(u8),
+    ShredStart,
+    ShredProgress(ShredProgress),
+    ShredCancel,
+    ShredFinished(Result<(),error::Error>),
+    ViewModeChanged(ViewMode),
+    ColorModeChanged(ColorMode),
+    OpenDir(PathBuf),
+    SelectPath(PathBuf),
+    SunburstDepth(usize),
+    SizeBarsToggled(bool),
+    TypeFilterSelected(TypeFilter),
+    FilterCleared,
+    StaleDays(u64),
+    StaleMinSize(MinSize),
+    StaleFilesShow,
+    LargestCount(usize),
+    LargestFilesShow,
+    DuplicateSearch,
+    DuplicateProgress(DuplicateProgress),
+    DuplicateFound(Result<Vec<DuplicateGroup>,error::Error>),
+    DuplicateKeep(usize, usize),
+    DuplicateActionSelected(DuplicateAction),
+    DuplicateApply,
+    DuplicateCancel,
+    DuplicatesResolved(Result<(),error::Error>),
+    EmptyRemove,
+    EmptyRemoved(Result<(),error::Error>),
+    JunkRuleToggled(String, bool),
+    JunkShow(String),
+    VolumesRefresh,
+    VolumesLoaded(Vec<Volume>),
+    VolumeOpen(PathBuf),
+    ExportFormatChanged(ExportFormat),
+    UnitStyleChanged(UnitStyle),
+    SortChanged(SortKey),
+    ThemeChanged(ThemeChoice),
+    LanguageChanged(Language),
+    ReportExport,
+    NcduImport,
+    NcduExport,
+    SnapshotSave,
+    SnapshotOpen,
+    SnapshotLoaded(Result<(PathBuf,Arc<ScanResult>,ScanOptions),error::Error>),
+    DiffOpen,
+    DiffLoaded(Result<(PathBuf,Arc<ScanResult>,ScanOptions),error::Error>),
 }
\ No newline at end of file
@@ -1,38 +1,99 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 use std::{
-    cmp::Ordering, env, fs::{self, Permissions}, path::{Path, PathBuf}, time::SystemTime, vec
+    fs, io::Write, path::{Path, PathBuf}, sync::Arc, time::SystemTime, vec
 };
 
 use filersmanager::{
+    analysis::{self, AgeHistogram, EmptyEntries, MinSize, TableFilter, TypeBreakdown},
+    batch_rename::{BatchRenameState, CaseConversion, PreviewStatus},
+    cli::{self, CliError, SortKey},
+    diff::{self, ChangeKind, DiffReport},
+    duplicate::{self, DuplicateAction, DuplicateState},
+    export::{ExportEntry, ExportFormat},
     error::Error,
-    file::{self, open_folder, output_folder_infos},
+    file::{self, open_folder, output_folder_infos, EntryType},
+    junk::{self, JunkConfig, JunkReport},
+    ncdu, report,
+    snapshot::{self, Snapshot},
+    i18n::{self, tr, tr_fmt, Language},
     icon,
-    widget::{FileTableRow, TableState},
+    scan::{self, ScanOptions, ScanResult, TableEntry},
+    settings::AppSettings,
+    theme::{self, ThemeChoice},
+    shred::{self, ShredState},
+    transfer::{self, TransferEvent, TransferKind, TransferState},
+    units::{self, format_size, UnitStyle},
+    volume::{self, Volume},
+    widget::{
+        new_entry_input_id, rename_input_id,
+        sunburst::{self, Sunburst},
+        treemap::{ColorMode, Treemap},
+        FileTableRow, TableState, ViewMode,
+    },
     Message,
 };
 use iced::{
-    executor, mouse, widget::{
-        button, column, container, responsive, row, scrollable, space, text, text_input, tooltip
-    }, Application, Command, Element, Font, Length, Settings, Theme
+    executor, keyboard, mouse, widget::{
+        button, canvas, checkbox, column, container, pick_list, progress_bar, radio, responsive, row, scrollable, space, text, text_input, tooltip
+    }, futures::channel::mpsc, window, Application, Command, Element, Font, Length, Point, Settings, Size, Theme
 };
 use iced_table::table;
 
-const ONE_KELO_BYTE: f32 = 1024.0;
-const SIX_DIGITS: u64 = 999999;
-const NINE_DIGITS: u64 = 999999999;
-
 fn main() -> iced::Result {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("error"));
 
+    //サブコマンドが指定された場合はウィンドウを開かずに実行する
+    let args: Vec<String> = std::env::args_os().skip(1).map(|arg| arg.to_string_lossy().into_owned()).collect();
+    match cli::parse_args(args) {
+        Ok(None) => {}
+        Ok(Some(command)) => {
+            cli::attach_console();
+            match cli::run(&command, &mut std::io::stdout().lock()) {
+                Ok(()) => std::process::exit(0),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Err(CliError::Help) => {
+            cli::attach_console();
+            let _ = writeln!(std::io::stdout(), "{}", CliError::Help);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            cli::attach_console();
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+
     let icon =
         iced::window::icon::from_file_data(include_bytes!("../assets/icon/frsm_icon.jpg"), None).unwrap();
+    let mut settings = AppSettings::load();
+    //パスを渡して起動された場合は前回のフォルダの代わりにそこを開く。ファイルならそれがあるフォルダ
+    if let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) {
+        let dir = if path.is_dir() { Some(path) } else { path.parent().map(Path::to_path_buf) };
+        if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+            settings.last_folder = Some(dir);
+        }
+    }
+    let geometry = settings.window.clone();
     AppState::run(Settings {
         fonts: vec![include_bytes!("../assets/font/iced-image.ttf").as_slice().into(),include_bytes!("../assets/font/07LogoTypeGothic7.ttf").as_slice().into()],
         default_font: Font::with_name("07ロゴたいぷゴシック7"),
         window: iced::window::Settings {
             icon: Some(icon),
+            size: Size::new(geometry.width, geometry.height),
+            position: match (geometry.x, geometry.y) {
+                (Some(x), Some(y)) => window::Position::Specific(Point::new(x as f32, y as f32)),
+                _ => window::Position::default(),
+            },
+            //閉じる前にウィンドウの位置と大きさを保存する
+            exit_on_close_request: false,
             ..Default::default()
         },
+        flags: settings,
         ..Default::default()
     })
 }
@@ -41,8 +102,39 @@ struct AppState {
     path: Option<PathBuf>,
     path_input_value: String,
     total_size:String,
-    file_info_vec: Vec<(PathBuf, u64,Permissions,Option<SystemTime>)>,
+    file_info_vec: Vec<TableEntry>,
+    scan: Option<Arc<ScanResult>>,
+    view_mode: ViewMode,
+    color_mode: ColorMode,
+    sunburst_depth: usize,
+    size_bars: bool,
+    type_breakdown: Option<TypeBreakdown>,
+    age_histogram: Option<AgeHistogram>,
+    empty_entries: Option<EmptyEntries>,
+    junk_config: JunkConfig,
+    junk_report: Option<JunkReport>,
+    volumes: Vec<Volume>,
+    export_format: ExportFormat,
+    settings: AppSettings,
+    theme: Theme,
+    //スナップショットやncduの読み込み結果を表示中ならそのファイルと読み込んだ結果
+    snapshot: Option<(PathBuf, SystemTime, ScanOptions)>,
+    //表示中のスキャン結果を作ったときの設定。スナップショットに一緒に保存する
+    scan_options: ScanOptions,
+    //ボリューム一覧から開いたときは他のマウントポイントに入らない
+    one_file_system: bool,
+    //比較に使ったスナップショットのファイル、その中身と比較結果
+    diff: Option<(PathBuf, Arc<ScanResult>, DiffReport)>,
+    stale_days: u64,
+    stale_min_size: MinSize,
+    largest_count: usize,
+    filter: Option<TableFilter>,
     table_state: TableState,
+    transfer: Option<TransferState>,
+    new_entry: Option<(EntryType, String)>,
+    batch_rename: Option<BatchRenameState>,
+    shred: Option<ShredState>,
+    duplicates: Option<DuplicateState>,
 }
 
 impl Application for AppState {
@@ -50,7 +142,7 @@ impl Application for AppState {
 
     type Message = Message;
 
-    type Flags = ();
+    type Flags = AppSettings;
 
     type Theme = Theme;
 
@@ -58,20 +150,86 @@ impl Application for AppState {
         String::from("FileRsManager")
     }
 
-    fn new(_flags: Self::Flags) -> (Self, iced::Command<Message>) {
+    fn new(settings: Self::Flags) -> (Self, iced::Command<Message>) {
+        units::set_unit_style(settings.unit_style);
+        i18n::set_language(settings.language.unwrap_or_else(Language::detect));
+        theme::apply_colors(&settings.colors);
+        let theme = ThemeChoice::from_name(&settings.theme).resolve(&settings.palette);
+        let mut table_state = TableState::new(None);
+        table_state.set_columns(&settings.columns);
+        //前回開いていたフォルダがまだあれば開き直す
+        let last_folder = settings.last_folder.clone().filter(|path| path.is_dir());
+        let scan_options = ScanOptions {
+            exclude: settings.exclude.clone(),
+            one_file_system: false,
+        };
+        let mut commands = vec![Command::perform(volume::volumes(), Message::VolumesLoaded)];
+        if let Some(path) = last_folder.clone() {
+            commands.push(Command::perform(scan::scan_with(path, scan_options.clone()), Message::Scanned));
+        }
         (
             Self {
-                path: Some(PathBuf::from("")),
+                path: Some(last_folder.clone().unwrap_or_default()),
                 path_input_value: String::new(),
                 total_size:String::new(),
                 file_info_vec: vec![],
-                table_state: TableState::new(None),
+                scan: None,
+                view_mode: if last_folder.is_some() { ViewMode::Table } else { ViewMode::Volumes },
+                color_mode: ColorMode::FileType,
+                sunburst_depth: 4,
+                size_bars: true,
+                type_breakdown: None,
+                age_histogram: None,
+                empty_entries: None,
+                junk_config: JunkConfig::load(),
+                junk_report: None,
+                volumes: vec![],
+                export_format: ExportFormat::Csv,
+                settings,
+                theme,
+                snapshot: None,
+                scan_options,
+                one_file_system: false,
+                diff: None,
+                stale_days: 365,
+                stale_min_size: MinSize(100 * 1024 * 1024),
+                largest_count: 100,
+                filter: None,
+                table_state,
+                transfer: None,
+                new_entry: None,
+                batch_rename: None,
+                shred: None,
+                duplicates: None,
             },
-            Command::none(),
+            Command::batch(commands),
         )
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        //スナップショットは実際のファイルと一致しないので変更を伴う操作は受け付けない
+        //読み込む前から開いていたダイアログの実行も止める
+        if self.snapshot.is_some()
+            && matches!(
+                message,
+                Message::Delete(_)
+                    | Message::RenameStart(_)
+                    | Message::RenameSubmit
+                    | Message::TransferStart(_)
+                    | Message::TransferDestinationPicked(..)
+                    | Message::NewEntryStart(_)
+                    | Message::NewEntrySubmit
+                    | Message::BatchRenameOpen
+                    | Message::BatchRenameApply
+                    | Message::ShredOpen
+                    | Message::ShredStart
+                    | Message::DuplicateSearch
+                    | Message::DuplicateApply
+                    | Message::EmptyRemove
+            )
+        {
+            return Command::none();
+        }
         match message {
             Message::OnInput(value) => {
                 //"c:/temp"=>c:/temp として扱う
@@ -90,16 +248,17 @@ impl Application for AppState {
                         if self.path_input_value.is_empty() {
                             let path = PathBuf::from(path);
                             return Command::perform(
-                                filesize_collect(path),
-                                Message::FileSerachedConvert,
+                                self.start_scan(path),
+                                Message::Scanned,
                             );
                         } else {
                             let path = PathBuf::from(&self.path_input_value);
                             if path.exists() {
                                 self.path = Some(path.clone());
+                                self.one_file_system = false;
                                 return Command::perform(
-                                    filesize_collect(path),
-                                    Message::FileSerachedConvert,
+                                    self.start_scan(path),
+                                    Message::Scanned,
                                 );
                             }
                         }
@@ -107,7 +266,7 @@ impl Application for AppState {
                 }
             }
             Message::FileSerachedConvert(mut value) => {
-                value.sort_by(|a, b| a.1.ancestor_cmp(&b.1));
+                self.settings.sort.sort(&mut value);
                 self.file_info_vec = value.clone();
                 return Command::perform(conv_fileinfovec_to_strvec(value), Message::FileSeached);
             }
@@ -121,18 +280,310 @@ impl Application for AppState {
             Message::FolderOpened(path) => {
                 if let Some(path) = path {
                     self.path = Some(path.clone());
-                    return Command::perform(filesize_collect(path), Message::FileSerachedConvert);
+                    self.one_file_system = false;
+                    return Command::perform(self.start_scan(path), Message::Scanned);
+                }
+            }
+            Message::Scanned(result) => {
+                //実際のフォルダをスキャンした場合だけ次回開くフォルダとして覚える
+                if self.settings.last_folder.as_ref() != Some(&result.root) {
+                    self.settings.last_folder = Some(result.root.clone());
+                    self.save_settings();
                 }
+                return self.show_result(result, None);
+            }
+            Message::ViewModeChanged(view_mode) => {
+                self.view_mode = view_mode;
+                self.refresh_analysis();
+            }
+            Message::TypeFilterSelected(filter) => {
+                return self.apply_filter(TableFilter::Type(filter));
+            }
+            Message::StaleDays(days) => {
+                self.stale_days = days;
+            }
+            Message::StaleMinSize(min_size) => {
+                self.stale_min_size = min_size;
+            }
+            Message::StaleFilesShow => {
+                if let Some(dir) = self.path.clone(){
+                    return self.apply_filter(TableFilter::Stale {
+                        dir,
+                        days: self.stale_days,
+                        min_size: self.stale_min_size.0,
+                    });
+                }
+            }
+            Message::LargestCount(count) => {
+                self.largest_count = count;
+            }
+            Message::LargestFilesShow => {
+                return self.apply_filter(TableFilter::Largest(self.largest_count));
+            }
+            Message::DuplicateSearch => {
+                if let (Some(result), false) = (self.scan.as_ref(), self.duplicates.as_ref().is_some_and(|state| state.running)){
+                    let candidates = duplicate::group_by_size(&result.tree, &result.root);
+                    let state = DuplicateState::new();
+                    let (sender,receiver) = mpsc::channel(100);
+                    let command = Command::batch(vec![
+                        Command::perform(
+                            duplicate::find(candidates, sender, state.cancel.clone()),
+                            Message::DuplicateFound,
+                        ),
+                        Command::run(receiver, Message::DuplicateProgress),
+                    ]);
+                    self.duplicates = Some(state);
+                    return command;
+                }
+            }
+            Message::DuplicateProgress(progress) => {
+                if let Some(state) = self.duplicates.as_mut(){
+                    state.checked = progress.checked;
+                    state.total = progress.total;
+                    state.current = progress.current;
+                }
+            }
+            Message::DuplicateFound(result) => {
+                match result {
+                    Ok(groups) => {
+                        if let Some(state) = self.duplicates.as_mut(){
+                            state.running = false;
+                            state.groups = groups;
+                        }
+                    }
+                    Err(Error::Cancelled) => self.duplicates = None,
+                    Err(e) => {
+                        self.duplicates = None;
+                        return self.update(Message::ErrorDialogShow(Err(e)));
+                    }
+                }
+            }
+            Message::DuplicateKeep(group, keep) => {
+                if let Some(group) = self.duplicates.as_mut().and_then(|state| state.groups.get_mut(group)){
+                    group.keep = keep;
+                }
+            }
+            Message::DuplicateActionSelected(action) => {
+                if let Some(state) = self.duplicates.as_mut(){
+                    state.action = action;
+                }
+            }
+            Message::DuplicateApply => {
+                if let Some(state) = self.duplicates.as_mut(){
+                    if !state.running && !state.groups.is_empty(){
+                        state.running = true;
+                        return Command::perform(
+                            duplicate::resolve(state.groups.clone(), state.action),
+                            Message::DuplicatesResolved,
+                        );
+                    }
+                }
+            }
+            Message::DuplicateCancel => {
+                if let Some(state) = self.duplicates.as_ref(){
+                    if state.running{
+                        state.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }else{
+                        self.duplicates = None;
+                    }
+                }
+            }
+            Message::DuplicatesResolved(result) => {
+                self.duplicates = None;
+                return Command::batch(vec![
+                    self.rescan(),
+                    self.update(Message::ErrorDialogShow(result)),
+                ]);
+            }
+            Message::EmptyRemove => {
+                if let Some(entries) = self.empty_entries.take(){
+                    return Command::perform(
+                        file::remove_empty_dialog(entries.dirs, entries.files),
+                        Message::EmptyRemoved,
+                    );
+                }
+            }
+            Message::EmptyRemoved(result) => {
+                return Command::batch(vec![
+                    self.rescan(),
+                    self.update(Message::ErrorDialogShow(result)),
+                ]);
+            }
+            Message::JunkRuleToggled(name, enabled) => {
+                self.junk_config.set_enabled(&name, enabled);
+                self.refresh_analysis();
+                if let Err(e) = self.junk_config.save(){
+                    return self.update(Message::ErrorDialogShow(Err(e)));
+                }
+            }
+            Message::JunkShow(name) => {
+                let rules = self.junk_config.enabled_rules();
+                if let Some(rule) = rules.iter().position(|rule| rule.name == name){
+                    return self.apply_filter(TableFilter::Junk { rules, rule });
+                }
+            }
+            Message::VolumesRefresh => {
+                return Command::perform(volume::volumes(), Message::VolumesLoaded);
+            }
+            Message::VolumesLoaded(volumes) => {
+                self.volumes = volumes;
+            }
+            Message::VolumeOpen(path) => {
+                self.view_mode = ViewMode::Table;
+                self.path_input_value = path.display().to_string();
+                self.path = Some(path.clone());
+                self.one_file_system = true;
+                return Command::perform(self.start_scan(path), Message::Scanned);
+            }
+            Message::FilterCleared => {
+                if let Some(path) = self.path.clone(){
+                    return self.show_dir(path);
+                }
+            }
+            Message::ColorModeChanged(color_mode) => {
+                self.color_mode = color_mode;
+            }
+            Message::OpenDir(path) => {
+                //比較結果から開いた場合はテーブルで中身を見せる
+                if self.view_mode == ViewMode::Diff {
+                    self.view_mode = ViewMode::Table;
+                }
+                return self.show_dir(path);
+            }
+            Message::SelectPath(path) => {
+                if let Some(row) = self.table_state.rows.iter_mut().find(|row| row.get_filepath() == path){
+                    row.selected = !row.selected;
+                }
+            }
+            Message::SunburstDepth(depth) => {
+                self.sunburst_depth = depth;
+            }
+            Message::SizeBarsToggled(show) => {
+                self.size_bars = show;
+                self.table_state.set_size_bars(show);
             }
             Message::OutputFileInfos => {
-                let text = self.table_state.rows.iter().map(|row|{row.to_string()}).collect::<Vec<String>>().join("\n");
-                if text.is_empty() {
+                //スナップショットの行は手元のファイルを調べず、読み込んだツリーの情報で出力する
+                let tree = self.scan.as_ref().filter(|_| self.snapshot.is_some());
+                let entries: Vec<ExportEntry> = self.table_state.rows.iter().map(|row| {
+                    match tree.and_then(|result| result.node(&row.get_filepath())) {
+                        Some(node) => ExportEntry::from_node(row.get_filepath(), node),
+                        None => ExportEntry::new(row.get_filepath(), row.bytes()),
+                    }
+                }).collect();
+                if entries.is_empty() {
                     return Command::none();
                 } else {
-                    return Command::perform(output_folder_infos(text), Message::ErrorDialogShow);
+                    return Command::perform(output_folder_infos(entries, self.export_format), Message::ErrorDialogShow);
+                }
+            }
+            Message::ExportFormatChanged(format) => {
+                self.export_format = format;
+            }
+            Message::UnitStyleChanged(style) => {
+                self.settings.unit_style = style;
+                self.save_settings();
+                units::set_unit_style(style);
+                //サイズの文字列はテーブルの行を作るときに決まるので作り直す
+                return self.update(Message::FileSerachedConvert(self.file_info_vec.clone()));
+            }
+            Message::ThemeChanged(choice) => {
+                self.theme = choice.resolve(&self.settings.palette);
+                self.settings.theme = choice.to_string();
+                self.save_settings();
+            }
+            Message::LanguageChanged(language) => {
+                self.settings.language = Some(language);
+                self.save_settings();
+                i18n::set_language(language);
+            }
+            Message::SortChanged(sort) => {
+                self.settings.sort = sort;
+                self.save_settings();
+                return self.update(Message::FileSerachedConvert(self.file_info_vec.clone()));
+            }
+            Message::ReportExport => {
+                if let Some(result) = self.scan.as_ref(){
+                    let dir = self.path.clone().filter(|path| result.contains(path)).unwrap_or(result.root.clone());
+                    let html = report::html_report(result, &dir, self.color_mode, SystemTime::now());
+                    return Command::perform(file::save_as(html, "report.html"), Message::ErrorDialogShow);
+                }
+            }
+            Message::NcduImport => {
+                return Command::perform(file::import_ncdu(), Message::SnapshotLoaded);
+            }
+            Message::SnapshotLoaded(result) => {
+                match result {
+                    Ok((file, result, options)) => {
+                        //開いていた編集用のダイアログは実際のファイルに対するものなので閉じる
+                        self.new_entry = None;
+                        self.batch_rename = None;
+                        if self.shred.as_ref().is_some_and(|state| !state.running){
+                            self.shred = None;
+                        }
+                        if let Some(state) = self.duplicates.take(){
+                            state.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                        }
+                        //読み込んだツリーのルートから表示する
+                        self.path = Some(result.root.clone());
+                        self.path_input_value = result.root.display().to_string();
+                        let scanned_at = result.scanned_at;
+                        self.scan_options = options.clone();
+                        return self.show_result(result, Some((file, scanned_at, options)));
+                    }
+                    Err(Error::Cancelled) => {}
+                    Err(e) => return self.update(Message::ErrorDialogShow(Err(e))),
+                }
+            }
+            Message::SnapshotSave => {
+                if let Some(result) = self.scan.as_ref(){
+                    return match snapshot::to_bytes(&Snapshot::new(result.as_ref().clone(), self.scan_options.clone())) {
+                        Ok(bytes) => Command::perform(file::save_as(bytes, snapshot::FILE_NAME), Message::ErrorDialogShow),
+                        Err(e) => self.update(Message::ErrorDialogShow(Err(e))),
+                    };
+                }
+            }
+            Message::DiffOpen => {
+                if self.scan.is_some(){
+                    return Command::perform(file::open_snapshot(), Message::DiffLoaded);
+                }
+            }
+            Message::DiffLoaded(result) => {
+                //開いたスナップショットを古い側として、表示中のスキャン結果と比べる
+                let report = result.and_then(|(file, old, _)| match self.scan.as_ref() {
+                    Some(new) => diff::diff(&old, new).map(|report| (file, old, report)),
+                    None => Err(Error::Cancelled),
+                });
+                match report {
+                    Ok(report) => {
+                        self.diff = Some(report);
+                        self.view_mode = ViewMode::Diff;
+                    }
+                    Err(Error::Cancelled) => {}
+                    Err(e) => return self.update(Message::ErrorDialogShow(Err(e))),
+                }
+            }
+            Message::SnapshotOpen => {
+                return Command::perform(file::open_snapshot(), Message::SnapshotLoaded);
+            }
+            Message::NcduExport => {
+                if let Some(result) = self.scan.as_ref(){
+                    return Command::perform(file::save_as(ncdu::export(result), "ncdu.json"), Message::ErrorDialogShow);
                 }
             }
             Message::EventOccured(event) => match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => match key {
+                    keyboard::Key::Named(keyboard::key::Named::F2) => {
+                        if let Some(index) = self.table_state.selected_index() {
+                            return self.update(Message::RenameStart(index));
+                        }
+                    }
+                    keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                        self.new_entry = None;
+                        return self.update(Message::RenameCancel);
+                    }
+                    _ => {}
+                },
                 iced::Event::Keyboard(_) => {}
                 iced::Event::Mouse(mouse_event) => match mouse_event {
                     mouse::Event::CursorEntered => {}
@@ -145,7 +596,21 @@ impl Application for AppState {
                     mouse::Event::ButtonReleased(_) => {}
                     mouse::Event::WheelScrolled { delta: _ } => {}
                 },
-                iced::Event::Window(_, _) => {}
+                iced::Event::Window(id, window_event) => match window_event {
+                    window::Event::Resized { width, height } => {
+                        self.settings.window.width = width as f32;
+                        self.settings.window.height = height as f32;
+                    }
+                    window::Event::Moved { x, y } => {
+                        self.settings.window.x = Some(x);
+                        self.settings.window.y = Some(y);
+                    }
+                    window::Event::CloseRequested => {
+                        self.save_settings();
+                        return window::close(id);
+                    }
+                    _ => {}
+                },
                 iced::Event::Touch(_) => {}
             },
             Message::ErrorDialogShow(result) => {
@@ -171,11 +636,308 @@ impl Application for AppState {
                     if let Some(offset) = column.resize_offset.take(){
                         column.width+=offset;
                     }
-            })}
+                });
+                for column in &self.table_state.columns {
+                    self.settings.set_column_width(column.kind, column.width);
+                }
+                self.save_settings();
+            }
             Message::Delete(index)=>{
-                let path = self.table_state.rows[index].get_filepath();
-                self.table_state.rows.remove(index);
-                return Command::perform(file::remove_file_dialog(path), Message::ErrorDialogShow);
+                let path = self.table_state.remove_row(index).get_filepath();
+                return Command::perform(file::remove_file_dialog(path.clone()), move |result| Message::Deleted(path.clone(),result));
+            }
+            Message::Deleted(path,result)=>{
+                //確認ダイアログで削除された場合だけツリーからも取り除く
+                if !path.exists(){
+                    self.file_info_vec.retain(|info| info.0 != path);
+                    if let Some(result) = self.scan.as_mut(){
+                        Arc::make_mut(result).remove(&path);
+                    }
+                }
+                return self.update(Message::ErrorDialogShow(result));
+            }
+            Message::Select(index,checked)=>{
+                if let Some(row) = self.table_state.rows.get_mut(index){
+                    row.selected = checked;
+                }
+            }
+            Message::RenameStart(index)=>{
+                self.table_state.cancel_rename();
+                if let Some(row) = self.table_state.rows.get_mut(index){
+                    row.rename_value = Some(row.file_name());
+                    return Command::batch(vec![
+                        text_input::focus(rename_input_id()),
+                        text_input::select_all(rename_input_id()),
+                    ]);
+                }
+            }
+            Message::RenameInput(value)=>{
+                if let Some(index) = self.table_state.renaming_index(){
+                    self.table_state.rows[index].rename_value = Some(value);
+                }
+            }
+            Message::RenameSubmit=>{
+                if let Some(index) = self.table_state.renaming_index(){
+                    let row = &mut self.table_state.rows[index];
+                    let new_name = row.rename_value.clone().unwrap_or_default();
+                    if new_name == row.file_name(){
+                        row.rename_value = None;
+                        return Command::none();
+                    }
+                    let path = row.get_filepath();
+                    return match file::validate_new_name(&path, &new_name) {
+                        Ok(new_path)=>Command::perform(file::rename_entry(path, new_path), Message::Renamed),
+                        Err(e)=>self.update(Message::ErrorDialogShow(Err(e))),
+                    };
+                }
+            }
+            Message::RenameCancel=>{
+                self.table_state.cancel_rename();
+            }
+            Message::Renamed(result)=>{
+                match result {
+                    Ok((from,to))=>{
+                        //再スキャンせずに該当行だけ更新する
+                        if let Some(row) = self.table_state.rows.iter_mut().find(|row| row.get_filepath() == from){
+                            row.set_filepath(to.clone());
+                            row.rename_value = None;
+                        }
+                        if let Some(result) = self.scan.as_mut(){
+                            Arc::make_mut(result).rename(&from, &to);
+                        }
+                        if let Some(info) = self.file_info_vec.iter_mut().find(|info| info.0 == from){
+                            info.0 = to;
+                        }
+                    }
+                    Err(e)=>return self.update(Message::ErrorDialogShow(Err(e))),
+                }
+            }
+            Message::TransferStart(kind)=>{
+                if self.transfer.is_none() && self.table_state.selected_index().is_some(){
+                    return Command::perform(file::pick_destination_folder(), move |path| {
+                        Message::TransferDestinationPicked(kind, path)
+                    });
+                }
+            }
+            Message::TransferDestinationPicked(kind,path)=>{
+                if let (Some(destination),None) = (path,self.transfer.as_ref()){
+                    let sources = self.table_state.rows.iter().filter(|row| row.selected).map(|row| row.get_filepath()).collect();
+                    let state = TransferState::new(kind);
+                    let (sender,receiver) = mpsc::channel(100);
+                    let command = Command::batch(vec![
+                        Command::perform(
+                            transfer::run(kind, sources, destination, sender, state.cancel.clone()),
+                            Message::TransferFinished,
+                        ),
+                        Command::run(receiver, Message::TransferEvent),
+                    ]);
+                    self.transfer = Some(state);
+                    return command;
+                }
+            }
+            Message::TransferEvent(event)=>{
+                if let Some(state) = self.transfer.as_mut(){
+                    match event {
+                        TransferEvent::Progress { copied, total, current }=>{
+                            state.copied = copied;
+                            state.total = total;
+                            state.current = current;
+                        }
+                        TransferEvent::Conflict(path, responder)=>{
+                            state.conflict = Some((path,responder));
+                        }
+                    }
+                }
+            }
+            Message::TransferConflictResolved(action)=>{
+                if let Some(state) = self.transfer.as_mut(){
+                    if let Some((_,responder)) = state.conflict.take(){
+                        responder.respond(action, state.apply_to_all);
+                    }
+                }
+            }
+            Message::TransferApplyToAll(checked)=>{
+                if let Some(state) = self.transfer.as_mut(){
+                    state.apply_to_all = checked;
+                }
+            }
+            Message::TransferCancel=>{
+                if let Some(state) = self.transfer.as_mut(){
+                    state.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                    if let Some((_,responder)) = state.conflict.take(){
+                        responder.respond(transfer::ConflictAction::Skip, true);
+                    }
+                }
+            }
+            Message::NewEntryStart(entry_type)=>{
+                if self.current_dir().is_some(){
+                    self.new_entry = Some((entry_type, String::new()));
+                    return text_input::focus(new_entry_input_id());
+                }
+            }
+            Message::NewEntryInput(value)=>{
+                if let Some((_, name)) = self.new_entry.as_mut(){
+                    *name = value;
+                }
+            }
+            Message::NewEntrySubmit=>{
+                if let (Some((entry_type, name)), Some(dir)) = (self.new_entry.as_ref(), self.current_dir()){
+                    return match file::validate_entry_name(&dir, name) {
+                        Ok(path)=>Command::perform(file::create_entry(path, *entry_type), Message::NewEntryCreated),
+                        Err(e)=>self.update(Message::ErrorDialogShow(Err(e))),
+                    };
+                }
+            }
+            Message::NewEntryCancel=>{
+                self.new_entry = None;
+            }
+            Message::NewEntryCreated(result)=>{
+                match result {
+                    Ok(path)=>{
+                        self.new_entry = None;
+                        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                        let is_dir = path.is_dir();
+                        self.file_info_vec.push((path.clone(), 0, modified, is_dir));
+                        if let Some(result) = self.scan.as_mut(){
+                            Arc::make_mut(result).insert(&path);
+                        }
+                        self.table_state.insert_sorted(FileTableRow::generate(path, 0, format_size(0), modified, is_dir), self.settings.sort);
+                    }
+                    Err(e)=>return self.update(Message::ErrorDialogShow(Err(e))),
+                }
+            }
+            Message::BatchRenameOpen=>{
+                let sources: Vec<PathBuf> = self.table_state.rows.iter().filter(|row| row.selected).map(|row| row.get_filepath()).collect();
+                if !sources.is_empty(){
+                    self.batch_rename = Some(BatchRenameState::new(sources));
+                }
+            }
+            Message::BatchRenameFind(value)=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    state.options.find = value;
+                    state.refresh();
+                }
+            }
+            Message::BatchRenameReplace(value)=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    state.options.replace = value;
+                    state.refresh();
+                }
+            }
+            Message::BatchRenameRegex(checked)=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    state.options.use_regex = checked;
+                    state.refresh();
+                }
+            }
+            Message::BatchRenameTemplate(value)=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    state.options.template = value;
+                    state.refresh();
+                }
+            }
+            Message::BatchRenameCase(case)=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    state.options.case = case;
+                    state.refresh();
+                }
+            }
+            Message::BatchRenameApply=>{
+                if let Some(state) = self.batch_rename.as_mut(){
+                    //適用直前にもう一度衝突を確認する
+                    state.refresh();
+                    if state.can_apply(){
+                        return Command::perform(filersmanager::batch_rename::apply(state.previews.clone()), Message::BatchRenamed);
+                    }
+                }
+            }
+            Message::BatchRenameCancel=>{
+                self.batch_rename = None;
+            }
+            Message::BatchRenamed(result)=>{
+                self.batch_rename = None;
+                match result {
+                    Ok(renamed)=>{
+                        for (from,to) in renamed {
+                            if let Some(row) = self.table_state.rows.iter_mut().find(|row| row.get_filepath() == from){
+                                row.set_filepath(to.clone());
+                            }
+                            if let Some(result) = self.scan.as_mut(){
+                                Arc::make_mut(result).rename(&from, &to);
+                            }
+                            if let Some(info) = self.file_info_vec.iter_mut().find(|info| info.0 == from){
+                                info.0 = to;
+                            }
+                        }
+                    }
+                    //戻しきれなかった変更があってもテーブルが実際の状態に合うようにスキャンし直す
+                    Err(e)=>return Command::batch([self.update(Message::ErrorDialogShow(Err(e))),self.rescan()]),
+                }
+            }
+            Message::ShredOpen=>{
+                let sources: Vec<PathBuf> = self.table_state.rows.iter().filter(|row| row.selected).map(|row| row.get_filepath()).collect();
+                if self.shred.is_none() && !sources.is_empty(){
+                    self.shred = Some(ShredState::new(sources));
+                }
+            }
+            Message::ShredPasses(passes)=>{
+                if let Some(state) = self.shred.as_mut(){
+                    state.passes = passes;
+                }
+            }
+            Message::ShredStart=>{
+                if let Some(state) = self.shred.as_mut(){
+                    if !state.running{
+                        state.running = true;
+                        let (sender,receiver) = mpsc::channel(100);
+                        return Command::batch(vec![
+                            Command::perform(
+                                shred::run(state.sources.clone(), state.passes, sender, state.cancel.clone()),
+                                Message::ShredFinished,
+                            ),
+                            Command::run(receiver, Message::ShredProgress),
+                        ]);
+                    }
+                }
+            }
+            Message::ShredProgress(progress)=>{
+                if let Some(state) = self.shred.as_mut(){
+                    state.written = progress.written;
+                    state.total = progress.total;
+                    state.current = progress.current;
+                }
+            }
+            Message::ShredCancel=>{
+                if let Some(state) = self.shred.as_ref(){
+                    if state.running{
+                        state.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }else{
+                        self.shred = None;
+                    }
+                }
+            }
+            Message::ShredFinished(result)=>{
+                self.shred = None;
+                let result = match result {
+                    Err(Error::Cancelled) => Ok(()),
+                    result => result,
+                };
+                return Command::batch(vec![
+                    self.rescan(),
+                    self.update(Message::ErrorDialogShow(result)),
+                ]);
+            }
+            Message::TransferFinished(result)=>{
+                self.transfer = None;
+                //利用者が中断した場合はエラーとして表示しない
+                let result = match result {
+                    Err(Error::Cancelled) => Ok(()),
+                    result => result,
+                };
+                return Command::batch(vec![
+                    self.rescan(),
+                    self.update(Message::ErrorDialogShow(result)),
+                ]);
             }
         }
         Command::none()
@@ -183,17 +945,34 @@ impl Application for AppState {
 
     fn view(&self) -> iced::Element<'_, Message> {
         let path_input =
-            text_input("please input pass", &self.path_input_value).on_input(Message::OnInput);
+            text_input(tr("path.placeholder"), &self.path_input_value).on_input(Message::OnInput);
         let run_button = button(container(icon::search_icon())).on_press(Message::FileSearch);
 
         let top_control = row!(path_input, run_button);
+        //スナップショット表示中は変更を伴う操作のボタンを無効にする
+        let editable = self.snapshot.is_none();
         let sub_func = row!(
             create_tooltrip(
                 icon::open_folder_icon(),
-                "開きたいフォルダを選択",
+                tr("tooltip.open_folder"),
                 Some(Message::OpenFolder)
             ),
-            create_tooltrip(icon::output_icon(), "出力", Some(Message::OutputFileInfos)),
+            pick_list(ViewMode::ALL, Some(self.view_mode), Message::ViewModeChanged),
+            pick_list(ColorMode::ALL, Some(self.color_mode), Message::ColorModeChanged),
+            pick_list(sunburst::DEPTHS, Some(self.sunburst_depth), Message::SunburstDepth),
+            checkbox(tr("toolbar.size_bars"), self.size_bars).on_toggle(Message::SizeBarsToggled),
+            pick_list(UnitStyle::ALL, Some(self.settings.unit_style), Message::UnitStyleChanged),
+            pick_list(SortKey::ALL, Some(self.settings.sort), Message::SortChanged),
+            pick_list(ThemeChoice::all(), Some(ThemeChoice::from_name(&self.settings.theme)), Message::ThemeChanged),
+            pick_list(Language::ALL, Some(i18n::language()), Message::LanguageChanged),
+            create_tooltrip(text(tr("toolbar.largest")), tr("tooltip.largest"), self.scan.as_ref().map(|_| Message::LargestFilesShow)),
+            pick_list(analysis::LARGEST_COUNTS, Some(self.largest_count), Message::LargestCount),
+            create_tooltrip(text(tr("toolbar.new_folder")), tr("tooltip.new_folder"), editable.then_some(Message::NewEntryStart(EntryType::Dir))),
+            create_tooltrip(text(tr("toolbar.new_file")), tr("tooltip.new_file"), editable.then_some(Message::NewEntryStart(EntryType::File))),
+            create_tooltrip(text(tr("toolbar.batch_rename")), tr("tooltip.batch_rename"), editable.then_some(Message::BatchRenameOpen)),
+            create_tooltrip(text(tr("toolbar.shred")), tr("tooltip.shred"), editable.then_some(Message::ShredOpen)),
+            create_tooltrip(text(tr("toolbar.copy")), tr("tooltip.copy"), editable.then_some(Message::TransferStart(TransferKind::Copy))),
+            create_tooltrip(text(tr("toolbar.move")), tr("tooltip.move"), editable.then_some(Message::TransferStart(TransferKind::Move))),
         );
 
         let table = responsive(|size| {
@@ -206,7 +985,86 @@ impl Application for AppState {
             ).on_column_resize(Message::Resizing,Message::Resized)
             .min_width(size.width).into()
         });
-        let control = column!(top_control, sub_func, table,);
+        let export_func = row!(
+            create_tooltrip(icon::output_icon(), tr("tooltip.output"), Some(Message::OutputFileInfos)),
+            pick_list(ExportFormat::ALL, Some(self.export_format), Message::ExportFormatChanged),
+            create_tooltrip(text(tr("toolbar.report")), tr("tooltip.report"), self.scan.as_ref().map(|_| Message::ReportExport)),
+            create_tooltrip(text("ncdu"), tr("tooltip.ncdu_import"), Some(Message::NcduImport)),
+            create_tooltrip(text("ncdu -o"), tr("tooltip.ncdu_export"), self.scan.as_ref().map(|_| Message::NcduExport)),
+            create_tooltrip(text(tr("toolbar.save_snapshot")), tr("tooltip.save_snapshot"), self.scan.as_ref().map(|_| Message::SnapshotSave)),
+            create_tooltrip(text(tr("toolbar.open_snapshot")), tr("tooltip.open_snapshot"), Some(Message::SnapshotOpen)),
+            create_tooltrip(text(tr("toolbar.diff")), tr("tooltip.diff"), self.scan.as_ref().map(|_| Message::DiffOpen)),
+        );
+        let mut control = column!(top_control, sub_func, export_func);
+        if let Some((file, scanned_at, options)) = &self.snapshot {
+            control = control.push(text(tr_fmt(
+                "snapshot.banner",
+                &[&file.display(), &chrono::DateTime::<chrono::Local>::from(*scanned_at).format("%Y-%m-%d %H:%M:%S")],
+            )));
+            //除外やマウントポイントの扱いで結果が変わるのでスキャン時の設定も表示する
+            if !options.exclude.is_empty() {
+                control = control.push(text(tr_fmt("snapshot.exclude", &[&options.exclude.join(", ")])));
+            }
+            if options.one_file_system {
+                control = control.push(text(tr("snapshot.one_file_system")));
+            }
+        }
+        if let Some((entry_type, name)) = &self.new_entry {
+            control = control.push(new_entry_view(*entry_type, name));
+        }
+        if let Some(state) = &self.batch_rename {
+            control = control.push(batch_rename_view(state));
+        }
+        if let Some(state) = &self.shred {
+            control = control.push(shred_view(state));
+        }
+        if let Some(state) = &self.transfer {
+            control = control.push(transfer_view(state));
+        }
+        let content: Element<_> = match (self.view_mode, self.scan.as_ref(), self.path.as_ref()) {
+            (ViewMode::Treemap, Some(result), Some(path)) => canvas(Treemap {
+                result,
+                dir: path,
+                mode: self.color_mode,
+            })
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+            (ViewMode::Sunburst, Some(result), Some(path)) => canvas(Sunburst {
+                result,
+                dir: path,
+                max_depth: self.sunburst_depth,
+                mode: self.color_mode,
+                selected: self.table_state.rows.iter().filter(|row| row.selected).map(|row| row.get_filepath()).collect(),
+            })
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+            (ViewMode::Types, _, _) => match &self.type_breakdown {
+                Some(breakdown) => type_breakdown_view(breakdown),
+                None => text("").into(),
+            },
+            (ViewMode::Duplicates, Some(_), _) => duplicates_view(self.duplicates.as_ref(), editable),
+            (ViewMode::Volumes, _, _) => volumes_view(&self.volumes),
+            (ViewMode::Diff, _, _) => match &self.diff {
+                Some((file, _, report)) => diff_view(file, report),
+                None => text(tr("diff.hint")).into(),
+            },
+            (ViewMode::Junk, _, _) => match &self.junk_report {
+                Some(report) => junk_view(report),
+                None => text("").into(),
+            },
+            (ViewMode::Empty, _, _) => match &self.empty_entries {
+                Some(entries) => empty_view(entries, editable),
+                None => text("").into(),
+            },
+            (ViewMode::Age, _, _) => match &self.age_histogram {
+                Some(histogram) => age_view(histogram, self.stale_days, self.stale_min_size),
+                None => text("").into(),
+            },
+            _ => table.into(),
+        };
+        let control = control.push(content);
         if self.file_info_vec.is_empty() {
             container(control).into()
         } else {
@@ -215,7 +1073,8 @@ impl Application for AppState {
                 row!(
                     text(self.path.as_ref().unwrap().display()),
                     space::Space::with_width(Length::Fill),
-                    text(format!("total:{}",&self.total_size)),
+                    filter_view(self.filter.as_ref()),
+                    text(tr_fmt("footer.total", &[&self.total_size])),
                 )
             ))
             .into()
@@ -227,85 +1086,479 @@ impl Application for AppState {
     }
 
     fn theme(&self) -> Self::Theme {
-        Theme::Dark
+        self.theme.clone()
     }
 }
 
 
-async fn conv_fileinfovec_to_strvec(vec: Vec<(PathBuf, u64,Permissions,Option<SystemTime>)>) ->(Vec<FileTableRow>,String) {
+async fn conv_fileinfovec_to_strvec(vec: Vec<TableEntry>) ->(Vec<FileTableRow>,String) {
     let mut total_size = 0;
     let fileinfo_str_vec = vec
         .into_iter()
-        .map(|(filename,size,perm,time)| {
-            let size_str = calc_unit(size);
+        .map(|(filename,size,time,is_dir)| {
+            let size_str = format_size(size);
             total_size += size;
 
-            FileTableRow::generate(filename, size_str,time)
+            FileTableRow::generate(filename, size, size_str,time,is_dir)
         })
         .collect();
 
-    (fileinfo_str_vec,calc_unit(total_size))
+    (fileinfo_str_vec,format_size(total_size))
 }
 
-fn calc_unit(size:u64)->String{
-    let fsize= size as f32;
-    if size <= SIX_DIGITS{
-        let kb_size = fsize / ONE_KELO_BYTE;
-        format!("{:.2}kb",kb_size)
-    }else if size <= NINE_DIGITS{
-        let mb_size = fsize / (ONE_KELO_BYTE * ONE_KELO_BYTE);
-        format!("{:.2}MB",mb_size)
-    }else{
-        let gb_size = fsize / (ONE_KELO_BYTE * ONE_KELO_BYTE * ONE_KELO_BYTE);
-        format!("{:.2}GB",gb_size)
+impl AppState {
+    //表示中のフォルダ
+    fn current_dir(&self) -> Option<PathBuf> {
+        self.path.clone().filter(|path| path.is_dir())
     }
-}
 
-fn serach_file<P>(path: P) -> u64
-where
-    P: AsRef<Path>,
-{
-    let mut fsize = 0;
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Ok(meta) = entry.metadata() {
-                    if meta.is_file() {
-                        fsize += meta.len();
-                    } else if meta.is_dir() {
-                        fsize += serach_file(entry.path());
-                    }
-                }
+    //スキャン結果、またはスナップショットやncduから読み込んだ結果を表示する
+    fn show_result(&mut self, result: Arc<ScanResult>, snapshot: Option<(PathBuf, SystemTime, ScanOptions)>) -> Command<Message> {
+        self.table_state.set_read_only(snapshot.is_some());
+        self.snapshot = snapshot;
+        //スキャンし直すたびに比較し直す。比較元と同じフォルダでなければ比較結果は意味を持たない
+        if let Some((file, old, _)) = self.diff.take(){
+            self.diff = diff::diff(&old, &result).ok().map(|report| (file, old, report));
+        }
+        //スキャン範囲外にいる場合はスキャンしたフォルダを表示する
+        let path = self.path.clone().filter(|path| result.contains(path)).unwrap_or(result.root.clone());
+        self.scan = Some(result);
+        let command = self.show_dir(path);
+        self.refresh_analysis();
+        command
+    }
+
+    //スキャン済みのフォルダなら再スキャンせずにテーブルを切り替える
+    fn show_dir(&mut self, path: PathBuf) -> Command<Message> {
+        self.filter = None;
+        self.table_state.set_full_path(false);
+        let children = self.scan.as_ref().and_then(|result| result.children_of(&path));
+        match children {
+            Some(children) => {
+                self.path = Some(path);
+                self.update(Message::FileSerachedConvert(children))
+            }
+            //スナップショットの外は実際のファイルをスキャンすることになるので移動しない
+            None if self.snapshot.is_some() => Command::none(),
+            None => {
+                self.path = Some(path.clone());
+                Command::perform(self.start_scan(path), Message::Scanned)
             }
         }
     }
-    return fsize;
-}
-async fn filesize_collect<P>(path: P) -> Vec<(PathBuf, u64,Permissions,Option<SystemTime>)>
-where
-    P: AsRef<Path>,
-{
-    let mut file_info_vec = vec![];
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Ok(meta) = entry.metadata() {
-                    let accessed_time = match meta.accessed() {
-                        Ok(time) => Some(time),
-                        Err(_) => None,
-                    };
-                    if meta.is_file() {
-                        file_info_vec.push((path, meta.len(),meta.permissions(),accessed_time));
-                    } else if meta.is_dir() {
-                        let total_size = serach_file(&path);
-                        file_info_vec.push((path, total_size,meta.permissions(),accessed_time));
-                    }
+
+    //ツリー全体から条件に合うファイルを集めてテーブルに表示する
+    fn apply_filter(&mut self, filter: TableFilter) -> Command<Message> {
+        let Some(result) = self.scan.as_ref() else {
+            return Command::none();
+        };
+        let files = filter.files(result, SystemTime::now());
+        self.table_state.set_full_path(true);
+        self.filter = Some(filter);
+        self.view_mode = ViewMode::Table;
+        self.update(Message::FileSerachedConvert(files))
+    }
+
+    //表示中の分析ビューだけを計算し直す
+    fn refresh_analysis(&mut self) {
+        self.type_breakdown = None;
+        self.age_histogram = None;
+        self.empty_entries = None;
+        self.junk_report = None;
+        let Some(result) = self.scan.as_ref() else {
+            return;
+        };
+        match self.view_mode {
+            ViewMode::Types => {
+                self.type_breakdown = Some(analysis::type_breakdown(&result.tree, &result.root));
+            }
+            ViewMode::Age => {
+                let node = self.path.as_ref().and_then(|path| result.node(path)).unwrap_or(&result.tree);
+                self.age_histogram = Some(analysis::age_histogram(node, SystemTime::now()));
+            }
+            ViewMode::Junk => {
+                self.junk_report = Some(junk::report(&result.tree, &result.root, &self.junk_config));
+            }
+            ViewMode::Empty => {
+                let path = self.path.clone().filter(|path| result.contains(path)).unwrap_or(result.root.clone());
+                if let Some(node) = result.node(&path) {
+                    self.empty_entries = Some(analysis::empty_entries(node, &path));
                 }
             }
+            _ => {}
+        }
+    }
+
+    fn start_scan(&mut self, path: PathBuf) -> impl std::future::Future<Output = Arc<ScanResult>> {
+        self.scan_options = ScanOptions {
+            exclude: self.settings.exclude.clone(),
+            one_file_system: self.one_file_system,
+        };
+        scan::scan_with(path, self.scan_options.clone())
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.settings.save() {
+            log::error!("{}", e);
         }
     }
-    file_info_vec
+
+    //ファイル操作の後にスキャンし直す
+    fn rescan(&mut self) -> Command<Message> {
+        match self.scan.as_ref().map(|result| result.root.clone()).or(self.current_dir()) {
+            Some(path) => Command::perform(self.start_scan(path), Message::Scanned),
+            None => Command::none(),
+        }
+    }
+}
+
+fn type_breakdown_view(breakdown: &TypeBreakdown) -> Element<'_, Message> {
+    let group_list = |groups: &[analysis::TypeGroup]| {
+        groups.iter().take(100).fold(column!(), |column, group| {
+            column.push(
+                button(text(tr_fmt(
+                    "types.row",
+                    &[
+                        &group.filter,
+                        &group.count,
+                        &format_size(group.bytes),
+                        &format!("{:.1}%", breakdown.percentage(group)),
+                    ],
+                )))
+                .on_press(Message::TypeFilterSelected(group.filter.clone()))
+                .width(Length::Fill),
+            )
+        })
+    };
+
+    row!(
+        column!(text(tr("types.category")), scrollable(group_list(&breakdown.categories))).width(Length::FillPortion(1)),
+        column!(text(tr("types.extension")), scrollable(group_list(&breakdown.extensions))).width(Length::FillPortion(1)),
+    )
+    .spacing(20)
+    .into()
+}
+
+fn age_view(histogram: &AgeHistogram, stale_days: u64, stale_min_size: MinSize) -> Element<'_, Message> {
+    let bars = histogram.groups.iter().fold(column!().spacing(5), |column, group| {
+        let percentage = histogram.percentage(group);
+        column.push(
+            row!(
+                text(group.bucket.to_string()).width(Length::Fixed(100.)),
+                progress_bar(0.0..=100.0, percentage as f32).height(Length::Fixed(16.)),
+                text(tr_fmt("age.row", &[&group.count, &format_size(group.bytes), &format!("{:.1}%", percentage)])).width(Length::Fixed(220.)),
+            )
+            .spacing(10),
+        )
+    });
+
+    column!(
+        bars,
+        row!(
+            text(tr("age.stale_prefix")),
+            pick_list(analysis::STALE_DAYS, Some(stale_days), Message::StaleDays),
+            text(tr("age.stale_days")),
+            pick_list(MinSize::ALL, Some(stale_min_size), Message::StaleMinSize),
+            text(tr("age.stale_size")),
+            button(tr("button.show")).on_press(Message::StaleFilesShow),
+        )
+        .spacing(10),
+    )
+    .spacing(20)
+    .into()
+}
+
+fn duplicates_view(state: Option<&DuplicateState>, editable: bool) -> Element<'_, Message> {
+    let Some(state) = state else {
+        return column!(
+            text(tr("duplicates.hint")),
+            button(tr("button.search")).on_press_maybe(editable.then_some(Message::DuplicateSearch)),
+        )
+        .spacing(10)
+        .into();
+    };
+    if state.running {
+        return row!(
+            text(format!("{}/{}  {}", state.checked, state.total, state.current.display())),
+            progress_bar(0.0..=1.0, state.ratio()).height(Length::Fixed(16.)),
+            button(tr("button.cancel")).on_press(Message::DuplicateCancel),
+        )
+        .spacing(10)
+        .into();
+    }
+
+    //大量にある場合は無駄の大きい組から表示する
+    let groups = state.groups.iter().enumerate().take(200).fold(column!().spacing(10), |column, (index, group)| {
+        let files = group.files.iter().enumerate().fold(column!(), |column, (file_index, path)| {
+            column.push(radio(path.display().to_string(), file_index, Some(group.keep), move |keep| {
+                Message::DuplicateKeep(index, keep)
+            }))
+        });
+        column.push(column!(
+            text(tr_fmt(
+                "duplicates.group",
+                &[&format_size(group.size), &group.files.len(), &format_size(group.wasted())],
+            )),
+            files,
+        ))
+    });
+
+    column!(
+        row!(
+            text(tr_fmt("duplicates.summary", &[&state.groups.len(), &format_size(state.wasted())])),
+            space::Space::with_width(Length::Fill),
+            text(tr("duplicates.action")),
+            pick_list(DuplicateAction::ALL, Some(state.action), Message::DuplicateActionSelected),
+            button(tr("button.apply")).on_press_maybe(editable.then_some(Message::DuplicateApply)),
+            button(tr("button.search_again")).on_press_maybe(editable.then_some(Message::DuplicateSearch)),
+            button(tr("button.close")).on_press(Message::DuplicateCancel),
+        )
+        .spacing(10),
+        scrollable(groups).height(Length::Fill),
+    )
+    .spacing(10)
+    .into()
+}
+
+fn empty_view(entries: &EmptyEntries, editable: bool) -> Element<'_, Message> {
+    if entries.is_empty() {
+        return text(tr("empty.none")).into();
+    }
+    let list = |title: &str, paths: &[PathBuf]| {
+        paths.iter().take(1000).fold(
+            column!(text(format!("{} ({})", title, paths.len()))),
+            |column, path| column.push(text(path.display())),
+        )
+    };
+
+    column!(
+        row!(
+            text(tr_fmt("empty.summary", &[&entries.dirs.len(), &entries.files.len()])),
+            button(tr("button.remove_all")).on_press_maybe(editable.then_some(Message::EmptyRemove)),
+        )
+        .spacing(10),
+        scrollable(column!(list(tr("empty.folders"), &entries.dirs), list(tr("empty.files"), &entries.files)).spacing(20))
+            .height(Length::Fill),
+    )
+    .spacing(10)
+    .into()
+}
+
+fn junk_view(report: &JunkReport) -> Element<'_, Message> {
+    let rules = report.rules.iter().fold(column!().spacing(5), |column, summary| {
+        let name = summary.rule.name.clone();
+        let status = match &summary.error {
+            Some(e) => text(e.to_string()),
+            None => text(tr_fmt("junk.count", &[&summary.count, &format_size(summary.bytes)])),
+        };
+        let show = button(tr("button.show")).on_press_maybe((summary.enabled && summary.count > 0).then(|| Message::JunkShow(name.clone())));
+        column.push(
+            row!(
+                checkbox(summary.rule.name.as_str(), summary.enabled)
+                    .on_toggle(move |enabled| Message::JunkRuleToggled(name.clone(), enabled))
+                    .width(Length::Fixed(200.)),
+                text(&summary.rule.pattern).width(Length::Fixed(300.)),
+                status.width(Length::Fill),
+                show,
+            )
+            .spacing(10),
+        )
+    });
+    let config = JunkConfig::path().map(|path| path.display().to_string()).unwrap_or_default();
+
+    column!(
+        text(tr_fmt("junk.total", &[&format_size(report.total)])),
+        scrollable(rules).height(Length::Fill),
+        text(tr_fmt("junk.custom_rules", &[&config])),
+    )
+    .spacing(10)
+    .into()
+}
+
+fn volumes_view(volumes: &[Volume]) -> Element<'_, Message> {
+    let list = volumes.iter().fold(column!().spacing(5), |column, volume| {
+        column.push(
+            button(
+                row!(
+                    text(volume.mount.mount_point.display()).width(Length::FillPortion(3)),
+                    text(format!("{} {}", volume.mount.fs_type, volume.mount.source)).width(Length::FillPortion(3)),
+                    progress_bar(0.0..=1.0, volume.ratio()).height(Length::Fixed(16.)).width(Length::FillPortion(3)),
+                    text(tr_fmt(
+                        "volumes.usage",
+                        &[&format_size(volume.used()), &format_size(volume.total), &format_size(volume.free)],
+                    ))
+                    .width(Length::FillPortion(3)),
+                )
+                .spacing(10),
+            )
+            .on_press(Message::VolumeOpen(volume.mount.mount_point.clone()))
+            .width(Length::Fill),
+        )
+    });
+
+    column!(
+        row!(
+            text(tr("volumes.hint")),
+            space::Space::with_width(Length::Fill),
+            button(tr("button.refresh")).on_press(Message::VolumesRefresh),
+        ),
+        scrollable(list).height(Length::Fill),
+    )
+    .spacing(10)
+    .into()
+}
+
+fn signed_unit(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(delta.unsigned_abs()))
+}
+
+fn diff_view<'a>(file: &'a std::path::Path, report: &'a DiffReport) -> Element<'a, Message> {
+    let format_time = |time: SystemTime| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M").to_string();
+    let header = text(tr_fmt(
+        "diff.header",
+        &[
+            &file.display(),
+            &format_time(report.old_scanned_at),
+            &format_time(report.new_scanned_at),
+            &format_size(report.root.old_size),
+            &format_size(report.root.new_size),
+            &signed_unit(report.root.delta()),
+            &format!("{:+}", report.root.file_delta()),
+        ],
+    ));
+
+    //変化の大きい順に上位だけを表示する
+    let list = report.entries.iter().take(500).fold(column!().spacing(2), |column, entry| {
+        let label = row!(
+            text(entry.kind.to_string()).width(Length::Fixed(70.)),
+            text(signed_unit(entry.delta())).width(Length::Fixed(110.)),
+            text(format!("{:+}", entry.file_delta())).width(Length::Fixed(80.)),
+            text(format!("{}{}", entry.path.display(), if entry.is_dir { "/" } else { "" })),
+        )
+        .spacing(10);
+        let open = (entry.is_dir && entry.kind != ChangeKind::Removed).then(|| Message::OpenDir(entry.path.clone()));
+        column.push(button(label).on_press_maybe(open).width(Length::Fill))
+    });
+
+    column!(header, scrollable(list).height(Length::Fill)).spacing(10).into()
+}
+
+fn filter_view(filter: Option<&TableFilter>) -> Element<'_, Message> {
+    match filter {
+        Some(filter) => row!(
+            text(tr_fmt("footer.filter", &[filter])),
+            button(tr("button.clear")).on_press(Message::FilterCleared),
+        )
+        .spacing(10)
+        .into(),
+        None => row!().into(),
+    }
+}
+
+fn new_entry_view(entry_type: EntryType, name: &str) -> Element<'_, Message> {
+    let placeholder = match entry_type {
+        EntryType::Dir => tr("new_entry.folder"),
+        EntryType::File => tr("new_entry.file"),
+    };
+    row!(
+        text_input(placeholder, name)
+            .id(new_entry_input_id())
+            .on_input(Message::NewEntryInput)
+            .on_submit(Message::NewEntrySubmit),
+        button(tr("button.create")).on_press(Message::NewEntrySubmit),
+        button(tr("button.cancel")).on_press(Message::NewEntryCancel),
+    )
+    .spacing(10)
+    .into()
+}
+
+fn batch_rename_view(state: &BatchRenameState) -> Element<'_, Message> {
+    let inputs = row!(
+        text_input(tr("rename.find"), &state.options.find).on_input(Message::BatchRenameFind),
+        text_input(tr("rename.replace"), &state.options.replace).on_input(Message::BatchRenameReplace),
+        checkbox(tr("rename.regex"), state.options.use_regex).on_toggle(Message::BatchRenameRegex),
+        text_input("{name}_{n:03}", &state.options.template).on_input(Message::BatchRenameTemplate),
+        pick_list(CaseConversion::ALL, Some(state.options.case), Message::BatchRenameCase),
+    )
+    .spacing(10);
+
+    let previews = state.previews.iter().fold(column!(), |column, preview| {
+        let status = match preview.status {
+            PreviewStatus::Ok => "",
+            PreviewStatus::Unchanged => tr("rename.unchanged"),
+            PreviewStatus::Invalid => tr("rename.invalid"),
+            PreviewStatus::Collision => tr("rename.collision"),
+        };
+        let from = preview.from.file_name().unwrap_or_default().to_string_lossy();
+        column.push(text(format!("{} → {} {}", from, preview.new_name(), status)))
+    });
+
+    let apply = button(tr("button.apply")).on_press_maybe(state.can_apply().then_some(Message::BatchRenameApply));
+    column!(
+        inputs,
+        text(state.error.as_deref().unwrap_or_default()),
+        scrollable(previews).height(Length::Fixed(160.)),
+        row!(apply, button(tr("button.cancel")).on_press(Message::BatchRenameCancel)).spacing(10),
+    )
+    .into()
+}
+
+fn shred_view(state: &ShredState) -> Element<'_, Message> {
+    if state.running {
+        return column!(
+            row!(
+                text(tr_fmt("shred.progress", &[&format_size(state.written), &format_size(state.total)])),
+                progress_bar(0.0..=1.0, state.ratio()).height(Length::Fixed(16.)),
+                button(tr("button.cancel")).on_press(Message::ShredCancel),
+            )
+            .spacing(10),
+            text(state.current.display()),
+        )
+        .into();
+    }
+
+    column!(
+        text(tr_fmt("shred.confirm", &[&state.sources.len()])),
+        text(tr("shred.warning")),
+        row!(
+            text(tr("shred.passes")),
+            pick_list(shred::PASSES, Some(state.passes), Message::ShredPasses),
+            button(tr("button.shred")).on_press(Message::ShredStart),
+            button(tr("button.cancel")).on_press(Message::ShredCancel),
+        )
+        .spacing(10),
+    )
+    .into()
+}
+
+fn transfer_view(state: &TransferState) -> Element<'_, Message> {
+    let label = match state.kind {
+        TransferKind::Copy => tr("transfer.copying"),
+        TransferKind::Move => tr("transfer.moving"),
+    };
+    let progress = row!(
+        text(format!("{} {}/{}", label, format_size(state.copied), format_size(state.total))),
+        progress_bar(0.0..=1.0, state.ratio()).height(Length::Fixed(16.)),
+        button(tr("button.cancel")).on_press(Message::TransferCancel),
+    )
+    .spacing(10);
+
+    match &state.conflict {
+        Some((path, _)) => column!(
+            progress,
+            text(tr_fmt("transfer.exists", &[&path.display()])),
+            row!(
+                button(tr("button.skip")).on_press(Message::TransferConflictResolved(transfer::ConflictAction::Skip)),
+                button(tr("button.overwrite")).on_press(Message::TransferConflictResolved(transfer::ConflictAction::Overwrite)),
+                button(tr("button.rename")).on_press(Message::TransferConflictResolved(transfer::ConflictAction::Rename)),
+                checkbox(tr("transfer.apply_to_all"), state.apply_to_all).on_toggle(Message::TransferApplyToAll),
+            )
+            .spacing(10),
+        )
+        .into(),
+        None => column!(progress, text(state.current.display())).into(),
+    }
 }
 
 fn create_tooltrip<'a>(
@@ -326,19 +1579,3 @@ fn create_tooltrip<'a>(
         btn.into()
     }
 }
-
-trait CmpExtension {
-    fn ancestor_cmp(&self, other: &u64) -> Ordering;
-}
-
-impl CmpExtension for u64 {
-    fn ancestor_cmp(&self, other: &u64) -> Ordering {
-        if *self < *other {
-            Ordering::Greater
-        } else if *self == *other {
-            Ordering::Equal
-        } else {
-            Ordering::Less
-        }
-    }
-}
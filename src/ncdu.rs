@@ -0,0 +1,126 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::Error,
+    scan::{ScanNode, ScanResult},
+};
+
+//ncdu -o の出力形式(メジャー1、マイナー2は mode/mtime などの拡張情報付き)
+const MAJOR_VERSION: u64 = 1;
+const MINOR_VERSION: u64 = 2;
+
+fn invalid(message: &str) -> Error {
+    Error::ImportFailed(message.into())
+}
+
+pub fn import(text: &str) -> Result<ScanResult, Error> {
+    let value: Value = serde_json::from_str(text).map_err(|e| Error::ImportFailed(e.to_string()))?;
+    let array = value.as_array().ok_or_else(|| invalid("not an ncdu export"))?;
+    if array.first().and_then(Value::as_u64) != Some(MAJOR_VERSION) {
+        return Err(invalid("unsupported ncdu export version"));
+    }
+    let scanned_at = array
+        .get(2)
+        .and_then(|meta| meta.get("timestamp"))
+        .and_then(Value::as_u64)
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap_or_else(SystemTime::now);
+    let mut tree = array.get(3).and_then(parse_entry).ok_or_else(|| invalid("missing root directory"))?;
+    if !tree.is_dir {
+        return Err(invalid("root is not a directory"));
+    }
+    tree.update_size();
+    tree.sort_by_size();
+
+    Ok(ScanResult {
+        root: PathBuf::from(&tree.name),
+        scanned_at,
+        tree,
+        revision: 0,
+    })
+}
+
+//フォルダは [情報, 子...] の配列、ファイルは情報のオブジェクトで表される
+fn parse_entry(value: &Value) -> Option<ScanNode> {
+    match value {
+        Value::Array(items) => {
+            let mut node = parse_info(items.first()?.as_object()?, true)?;
+            node.children = items[1..].iter().filter_map(parse_entry).collect();
+            Some(node)
+        }
+        Value::Object(info) => {
+            //除外されたものや通常ファイル以外はスキャン時と同じく扱わない
+            if info.contains_key("excluded") || info.get("notreg").and_then(Value::as_bool) == Some(true) {
+                return None;
+            }
+            parse_info(info, false)
+        }
+        _ => None,
+    }
+}
+
+fn parse_info(info: &Map<String, Value>, is_dir: bool) -> Option<ScanNode> {
+    Some(ScanNode {
+        name: info.get("name")?.as_str()?.to_string(),
+        size: if is_dir { 0 } else { info.get("asize").and_then(Value::as_u64).unwrap_or(0) },
+        is_dir,
+        mode: info.get("mode").and_then(Value::as_u64).map(|mode| mode as u32 & 0o7777).unwrap_or(0),
+        modified: info
+            .get("mtime")
+            .and_then(Value::as_u64)
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        accessed: None,
+        children: vec![],
+    })
+}
+
+pub fn export(result: &ScanResult) -> String {
+    let timestamp = result
+        .scanned_at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut root = result.tree.clone();
+    root.name = result.root.display().to_string();
+    let value = json!([
+        MAJOR_VERSION,
+        MINOR_VERSION,
+        {
+            "progname": "filersmanager",
+            "progver": env!("CARGO_PKG_VERSION"),
+            "timestamp": timestamp,
+        },
+        export_entry(&root),
+    ]);
+    value.to_string()
+}
+
+fn export_entry(node: &ScanNode) -> Value {
+    let mut info = Map::new();
+    info.insert("name".into(), node.name.clone().into());
+    if !node.is_dir {
+        //使用ブロック数は記録していないので見かけのサイズをそのまま使う
+        info.insert("asize".into(), node.size.into());
+        info.insert("dsize".into(), node.size.into());
+    }
+    if node.mode != 0 {
+        let kind = if node.is_dir { 0o040000 } else { 0o100000 };
+        info.insert("mode".into(), (kind | node.mode).into());
+    }
+    if let Some(mtime) = node.modified.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        info.insert("mtime".into(), mtime.as_secs().into());
+    }
+
+    if node.is_dir {
+        let mut items = vec![Value::Object(info)];
+        items.extend(node.children.iter().map(export_entry));
+        Value::Array(items)
+    } else {
+        Value::Object(info)
+    }
+}
@@ -0,0 +1,127 @@
+use std::{fmt::Write, path::Path, time::SystemTime};
+
+use iced::{Point, Rectangle, Size};
+
+use crate::{
+    analysis::{largest_files, percentage},
+    export::timestamp,
+    units::format_size,
+    scan::ScanResult,
+    theme::to_hex,
+    widget::treemap::{self, ColorMode},
+};
+
+const TREEMAP_WIDTH: f32 = 1200.0;
+const TREEMAP_HEIGHT: f32 = 640.0;
+const LARGEST: usize = 50;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:24px;color:#222}\
+table{border-collapse:collapse;margin-bottom:24px}\
+th,td{padding:4px 10px;border-bottom:1px solid #ddd;text-align:left}\
+td.num{text-align:right}\
+th{cursor:pointer;background:#f3f3f3}\
+svg rect{stroke:#fff;stroke-width:0.5}";
+
+//見出しをクリックすると data-value の値で並べ替える
+const SCRIPT: &str = "document.querySelectorAll('table.sortable th').forEach(function(th,i){\
+th.addEventListener('click',function(){\
+var body=th.closest('table').tBodies[0];var rows=Array.from(body.rows);\
+var asc=th.dataset.asc!=='1';th.dataset.asc=asc?'1':'0';\
+rows.sort(function(a,b){var x=a.cells[i].dataset.value,y=b.cells[i].dataset.value;\
+var n=parseFloat(x)-parseFloat(y);var c=isNaN(n)?x.localeCompare(y):n;return asc?c:-c;});\
+rows.forEach(function(r){body.appendChild(r);});});});";
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+//外部ファイルを参照しない一つのHTMLとして書き出す
+pub fn html_report(result: &ScanResult, dir: &Path, mode: ColorMode, now: SystemTime) -> String {
+    let Some(node) = result.node(dir) else {
+        return String::new();
+    };
+    let mut html = String::new();
+    let title = format!("Disk usage: {}", dir.display());
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>",
+        escape(&title),
+        STYLE
+    );
+    let _ = write!(
+        html,
+        "<h1>{}</h1><p>scan root: {}<br>scanned at: {}<br>total: {} ({} bytes, {} files)</p>",
+        escape(&title),
+        escape(&result.root.display().to_string()),
+        timestamp(result.scanned_at),
+        format_size(node.size),
+        node.size,
+        node.file_count()
+    );
+
+    html.push_str("<h2>Entries</h2><table class=\"sortable\"><thead><tr><th>name</th><th>type</th><th>size</th><th>%</th><th>modified</th></tr></thead><tbody>");
+    for child in &node.children {
+        let modified = child.modified.map(timestamp).unwrap_or_default();
+        let _ = write!(
+            html,
+            "<tr><td data-value=\"{name}\">{name}</td><td data-value=\"{kind}\">{kind}</td><td class=\"num\" data-value=\"{bytes}\">{size}</td><td class=\"num\" data-value=\"{bytes}\">{percent:.1}</td><td data-value=\"{modified}\">{modified}</td></tr>",
+            name = escape(&child.name),
+            kind = if child.is_dir { "dir" } else { "file" },
+            bytes = child.size,
+            size = format_size(child.size),
+            percent = percentage(child.size, node.size),
+            modified = modified,
+        );
+    }
+    html.push_str("</tbody></table>");
+
+    html.push_str("<h2>Largest files</h2><table class=\"sortable\"><thead><tr><th>path</th><th>size</th></tr></thead><tbody>");
+    for (path, size, _, _) in largest_files(node, dir, LARGEST) {
+        let path = escape(&path.strip_prefix(dir).unwrap_or(&path).display().to_string());
+        let _ = write!(
+            html,
+            "<tr><td data-value=\"{path}\">{path}</td><td class=\"num\" data-value=\"{bytes}\">{size}</td></tr>",
+            bytes = size,
+            size = format_size(size),
+        );
+    }
+    html.push_str("</tbody></table>");
+
+    html.push_str("<h2>Treemap</h2>");
+    html.push_str(&svg_treemap(result, dir, mode, now));
+    let _ = write!(html, "<script>{}</script></body></html>", SCRIPT);
+    html
+}
+
+//アプリのツリーマップと同じ配置をSVGで描く。マウスを重ねるとパスとサイズを表示する
+fn svg_treemap(result: &ScanResult, dir: &Path, mode: ColorMode, now: SystemTime) -> String {
+    let Some(node) = result.node(dir) else {
+        return String::new();
+    };
+    let bounds = Rectangle::new(Point::ORIGIN, Size::new(TREEMAP_WIDTH, TREEMAP_HEIGHT));
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = TREEMAP_WIDTH,
+        h = TREEMAP_HEIGHT
+    );
+    for tile in treemap::layout(node, dir, bounds, mode, now) {
+        let label = tile.path.strip_prefix(dir).unwrap_or(&tile.path).display().to_string();
+        let _ = write!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{} {}</title></rect>",
+            tile.rect.x,
+            tile.rect.y,
+            tile.rect.width,
+            tile.rect.height,
+            to_hex(tile.color),
+            escape(&label),
+            format_size(tile.size)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
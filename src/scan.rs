@@ -0,0 +1,305 @@
+use std::{
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::LargestFiles;
+
+//テーブルに並べるエントリ。パス、サイズ、日時、フォルダかどうか
+pub type TableEntry = (PathBuf, u64, Option<SystemTime>, bool);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanNode {
+    pub name: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub mode: u32,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub children: Vec<ScanNode>,
+}
+
+impl ScanNode {
+    fn from_metadata(name: String, meta: &Metadata) -> Self {
+        Self {
+            name,
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            is_dir: meta.is_dir(),
+            mode: permission_mode(meta),
+            modified: meta.modified().ok(),
+            accessed: meta.accessed().ok(),
+            children: vec![],
+        }
+    }
+
+    //rootからの相対パスで子孫を探す
+    pub fn find(&self, relative: &Path) -> Option<&ScanNode> {
+        let mut node = self;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy();
+            node = node.children.iter().find(|child| child.name == name)?;
+        }
+        Some(node)
+    }
+
+    pub fn find_mut(&mut self, relative: &Path) -> Option<&mut ScanNode> {
+        let mut node = self;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy();
+            node = node.children.iter_mut().find(|child| child.name == name)?;
+        }
+        Some(node)
+    }
+
+    pub fn file_count(&self) -> u64 {
+        if self.is_dir {
+            self.children.iter().map(ScanNode::file_count).sum()
+        } else {
+            1
+        }
+    }
+
+    //子孫のサイズを合計し直す
+    pub fn update_size(&mut self) {
+        if self.is_dir {
+            self.children.iter_mut().for_each(ScanNode::update_size);
+            self.size = self.children.iter().map(|child| child.size).sum();
+        }
+    }
+
+    pub fn sort_by_size(&mut self) {
+        self.children.sort_by_key(|child| std::cmp::Reverse(child.size));
+        self.children.iter_mut().for_each(ScanNode::sort_by_size);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub root: PathBuf,
+    pub scanned_at: SystemTime,
+    pub tree: ScanNode,
+    //名前変更や作成・削除でツリーを書き換えるたびに増やす。キャンバスの再計算に使う
+    #[serde(skip)]
+    pub revision: u64,
+}
+
+impl ScanResult {
+    pub fn node(&self, path: &Path) -> Option<&ScanNode> {
+        self.tree.find(path.strip_prefix(&self.root).ok()?)
+    }
+
+    pub fn node_mut(&mut self, path: &Path) -> Option<&mut ScanNode> {
+        let relative = path.strip_prefix(&self.root).ok()?.to_path_buf();
+        self.tree.find_mut(&relative)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.node(path).is_some()
+    }
+
+    //指定フォルダ直下のエントリをテーブル表示用に返す
+    pub fn children_of(&self, path: &Path) -> Option<Vec<TableEntry>> {
+        let node = self.node(path)?;
+        Some(
+            node.children
+                .iter()
+                .map(|child| (path.join(&child.name), child.size, child.modified, child.is_dir))
+                .collect(),
+        )
+    }
+
+    //名前変更や作成・削除をツリーにも反映する
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let (Some(parent), Some(name)) = (from.parent(), to.file_name()) else {
+            return;
+        };
+        if from.parent() != to.parent() {
+            return;
+        }
+        let old_name = from.file_name().map(|n| n.to_string_lossy().into_owned());
+        if let Some(parent) = self.node_mut(parent) {
+            if let Some(child) = parent.children.iter_mut().find(|c| Some(&c.name) == old_name.as_ref()) {
+                child.name = name.to_string_lossy().into_owned();
+            }
+        }
+        self.revision += 1;
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        let name = name.to_string_lossy();
+        if let Some(parent) = self.node_mut(parent) {
+            parent.children.retain(|child| child.name != name);
+        }
+        self.tree.update_size();
+        self.revision += 1;
+    }
+
+    pub fn insert(&mut self, path: &Path) {
+        let (Some(parent), Some(name), Ok(meta)) = (path.parent(), path.file_name(), fs::metadata(path)) else {
+            return;
+        };
+        let node = ScanNode::from_metadata(name.to_string_lossy().into_owned(), &meta);
+        if let Some(parent) = self.node_mut(parent) {
+            parent.children.push(node);
+        }
+        self.tree.update_size();
+        self.revision += 1;
+    }
+}
+
+pub async fn scan(path: PathBuf) -> Arc<ScanResult> {
+    scan_excluding(path, vec![]).await
+}
+
+//スキャン時の挙動
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    //名前が除外パターン(正規表現)のいずれかに一致するフォルダやファイルは数えない
+    pub exclude: Vec<String>,
+    //別のファイルシステムがマウントされたフォルダの中には入らない
+    pub one_file_system: bool,
+}
+
+pub async fn scan_excluding(path: PathBuf, exclude: Vec<String>) -> Arc<ScanResult> {
+    scan_with(
+        path,
+        ScanOptions {
+            exclude,
+            one_file_system: false,
+        },
+    )
+    .await
+}
+
+pub async fn scan_with(path: PathBuf, options: ScanOptions) -> Arc<ScanResult> {
+    let exclude = RegexSet::new(&options.exclude).unwrap_or_else(|e| {
+        log::error!("{}", e);
+        RegexSet::empty()
+    });
+    let scanned_at = SystemTime::now();
+    let mut device = None;
+    let mut tree = match fs::metadata(&path) {
+        Ok(meta) => {
+            device = options.one_file_system.then(|| device_id(&meta));
+            ScanNode::from_metadata(path.display().to_string(), &meta)
+        }
+        Err(_) => ScanNode {
+            name: path.display().to_string(),
+            size: 0,
+            is_dir: true,
+            mode: 0,
+            modified: None,
+            accessed: None,
+            children: vec![],
+        },
+    };
+    if tree.is_dir {
+        scan_dir(&path, &mut tree, &exclude, device);
+    }
+    tree.sort_by_size();
+
+    Arc::new(ScanResult {
+        root: path,
+        scanned_at,
+        tree,
+        revision: 0,
+    })
+}
+
+fn scan_dir(path: &Path, node: &mut ScanNode, exclude: &RegexSet, device: Option<u64>) {
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if exclude.is_match(&name) {
+                continue;
+            }
+            if let Ok(meta) = entry.metadata() {
+                let mut child = ScanNode::from_metadata(name, &meta);
+                if meta.is_dir() {
+                    //マウントポイントはフォルダとしては残すが中身は数えない
+                    if device.is_none_or(|device| device_id(&meta) == device) {
+                        scan_dir(&entry.path(), &mut child, exclude, device);
+                    }
+                } else if !meta.is_file() {
+                    continue;
+                }
+                node.size += child.size;
+                node.children.push(child);
+            }
+        }
+    }
+}
+
+//ツリーを作らずにディスクを辿り、大きい順にn件のファイルだけを返す
+pub async fn largest_files(mut path: PathBuf, n: usize, options: ScanOptions) -> Vec<TableEntry> {
+    let exclude = RegexSet::new(&options.exclude).unwrap_or_else(|e| {
+        log::error!("{}", e);
+        RegexSet::empty()
+    });
+    let device = fs::metadata(&path)
+        .ok()
+        .filter(|_| options.one_file_system)
+        .map(|meta| device_id(&meta));
+    let mut largest = LargestFiles::new(n);
+    largest_in_dir(&mut path, &mut largest, &exclude, device);
+    largest.into_entries()
+}
+
+fn largest_in_dir(path: &mut PathBuf, largest: &mut LargestFiles, exclude: &RegexSet, device: Option<u64>) {
+    let Ok(entries) = fs::read_dir(&*path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if exclude.is_match(&name.to_string_lossy()) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            if device.is_none_or(|device| device_id(&meta) == device) {
+                path.push(&name);
+                largest_in_dir(path, largest, exclude, device);
+                path.pop();
+            }
+        } else if meta.is_file() && largest.accepts(meta.len()) {
+            largest.push(meta.len(), path.join(&name), meta.modified().ok());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn device_id(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.dev()
+}
+
+#[cfg(not(unix))]
+fn device_id(_meta: &Metadata) -> u64 {
+    0
+}
+
+#[cfg(unix)]
+pub fn permission_mode(meta: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+pub fn permission_mode(meta: &Metadata) -> u32 {
+    if meta.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
@@ -0,0 +1,135 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::SortKey,
+    error::Error,
+    filetype::FileType,
+    i18n::Language,
+    theme::CustomPalette,
+    units::UnitStyle,
+    widget::{ColumnKind, ColumnSetting},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+    //前回の位置。なければウィンドウシステムに任せる
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: 1024.0,
+            height: 768.0,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+//起動をまたいで保持する表示設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    //"System"、iced組み込みテーマの名前、または "Custom"
+    pub theme: String,
+    pub palette: CustomPalette,
+    //未設定なら環境変数 LANG などから決める
+    pub language: Option<Language>,
+    //分類ごとの色の上書き(例: video = "#ff0000")
+    pub colors: BTreeMap<FileType, String>,
+    pub unit_style: UnitStyle,
+    pub sort: SortKey,
+    //スキャンで数えない名前の正規表現
+    pub exclude: Vec<String>,
+    pub last_folder: Option<PathBuf>,
+    pub window: WindowGeometry,
+    #[serde(rename = "column")]
+    pub columns: Vec<ColumnSetting>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            theme: "Dark".into(),
+            palette: CustomPalette::default(),
+            language: None,
+            colors: BTreeMap::new(),
+            unit_style: UnitStyle::default(),
+            sort: SortKey::default(),
+            exclude: vec![],
+            last_folder: None,
+            window: WindowGeometry::default(),
+            columns: ColumnKind::ALL.iter().copied().map(ColumnSetting::new).collect(),
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("filersmanager").join("settings.toml"))
+    }
+
+    //設定ファイルがない、または読めない場合は既定値で起動する
+    pub fn load() -> Self {
+        Self::load_with(true)
+    }
+
+    //CLIのように設定を保存しない場合は、壊れたファイルの退避も含めて何も書き込まない
+    pub fn load_read_only() -> Self {
+        Self::load_with(false)
+    }
+
+    fn load_with(backup: bool) -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        Self::parse(&text).unwrap_or_else(|e| {
+            log::error!("{}", e);
+            //既定値で保存し直すと手で書いた設定が消えるので、壊れたファイルを退避しておく
+            if backup {
+                if let Err(e) = fs::copy(&path, path.with_extension("toml.bak")) {
+                    log::error!("{}", e);
+                }
+            }
+            Self::default()
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut settings: Self = toml::from_str(text).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        //ファイルに書かれていない列は末尾に既定の幅で追加する
+        for kind in ColumnKind::ALL {
+            if !settings.columns.iter().any(|column| column.kind == *kind) {
+                settings.columns.push(ColumnSetting::new(*kind));
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let text = toml::to_string(self).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        }
+        fs::write(path, text).map_err(|e| Error::AsyncTokioIoError(e.kind()))
+    }
+
+    pub fn set_column_width(&mut self, kind: ColumnKind, width: f32) {
+        if let Some(column) = self.columns.iter_mut().find(|column| column.kind == kind) {
+            column.width = width;
+        }
+    }
+}
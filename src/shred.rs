@@ -0,0 +1,195 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_recursion::async_recursion;
+use iced::futures::{channel::mpsc, SinkExt};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use crate::error::Error;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+pub const PASSES: &[u8] = &[1, 3, 7];
+
+#[derive(Debug, Clone)]
+pub struct ShredProgress {
+    pub written: u64,
+    pub total: u64,
+    pub current: PathBuf,
+}
+
+pub struct ShredState {
+    pub sources: Vec<PathBuf>,
+    pub passes: u8,
+    pub running: bool,
+    pub written: u64,
+    pub total: u64,
+    pub current: PathBuf,
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl ShredState {
+    pub fn new(sources: Vec<PathBuf>) -> Self {
+        Self {
+            sources,
+            passes: 1,
+            running: false,
+            written: 0,
+            total: 0,
+            current: PathBuf::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.written as f32 / self.total as f32
+        }
+    }
+}
+
+struct Progress {
+    sender: mpsc::Sender<ShredProgress>,
+    cancel: Arc<AtomicBool>,
+    written: u64,
+    total: u64,
+}
+
+pub async fn run(
+    sources: Vec<PathBuf>,
+    passes: u8,
+    sender: mpsc::Sender<ShredProgress>,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), Error> {
+    let mut files = vec![];
+    for source in &sources {
+        collect_files(source, &mut files).await;
+    }
+    let total = files.iter().map(|(_, len)| *len).sum::<u64>() * passes as u64;
+    let mut progress = Progress {
+        sender,
+        cancel,
+        written: 0,
+        total,
+    };
+
+    //中断や失敗の時点で上書きを始めていたファイルは中身が壊れているので、削除まで済ませる
+    let mut touched = vec![];
+    for (path, len) in files {
+        if let Err(e) = overwrite(&path, len, passes, &mut progress, &mut touched).await {
+            for path in touched {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+            return Err(e);
+        }
+    }
+    for source in sources {
+        let meta = tokio::fs::symlink_metadata(&source)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        if meta.is_dir() {
+            tokio::fs::remove_dir_all(&source).await
+        } else {
+            tokio::fs::remove_file(&source).await
+        }
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    }
+    Ok(())
+}
+
+//シンボリックリンクは辿らず、通常ファイルだけを上書き対象にする
+#[async_recursion]
+async fn collect_files(path: &Path, files: &mut Vec<(PathBuf, u64)>) {
+    let Ok(meta) = tokio::fs::symlink_metadata(path).await else {
+        return;
+    };
+    if meta.is_file() {
+        files.push((path.to_path_buf(), meta.len()));
+    } else if meta.is_dir() {
+        if let Ok(mut entries) = tokio::fs::read_dir(path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                collect_files(&entry.path(), files).await;
+            }
+        }
+    }
+}
+
+async fn overwrite(
+    path: &Path,
+    len: u64,
+    passes: u8,
+    progress: &mut Progress,
+    touched: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    touched.push(path.to_path_buf());
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x2545_f491_4f6c_dd1d)
+        | 1;
+
+    for pass in 0..passes {
+        file.seek(SeekFrom::Start(0))
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(BUFFER_SIZE as u64) as usize;
+            fill(&mut buffer[..chunk], pass, passes, &mut seed);
+            file.write_all(&buffer[..chunk])
+                .await
+                .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+            remaining -= chunk as u64;
+
+            progress.written += chunk as u64;
+            let _ = progress
+                .sender
+                .send(ShredProgress {
+                    written: progress.written,
+                    total: progress.total,
+                    current: path.to_path_buf(),
+                })
+                .await;
+            if progress.cancel.load(Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+        }
+        //パスごとに実際にディスクへ書き出す
+        file.sync_all()
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    }
+    Ok(())
+}
+
+//最終パスはゼロ、それ以外は乱数と0xFFを交互に書き込む
+pub fn fill(buffer: &mut [u8], pass: u8, passes: u8, seed: &mut u64) {
+    if pass + 1 == passes && passes > 1 {
+        buffer.fill(0);
+    } else if pass % 2 == 1 {
+        buffer.fill(0xff);
+    } else {
+        for chunk in buffer.chunks_mut(8) {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            let bytes = seed.to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    io::{Read, Write},
+    time::SystemTime,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    scan::{ScanOptions, ScanResult},
+};
+
+pub const FILE_NAME: &str = "scan.frsnap.gz";
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub program: String,
+    pub saved_at: SystemTime,
+    //スキャン時の挙動。読み込んだ側が結果の意味を取り違えないよう一緒に保存する
+    #[serde(default)]
+    pub options: ScanOptions,
+    pub result: ScanResult,
+}
+
+impl Snapshot {
+    pub fn new(result: ScanResult, options: ScanOptions) -> Self {
+        Self {
+            version: VERSION,
+            program: format!("filersmanager {}", env!("CARGO_PKG_VERSION")),
+            saved_at: SystemTime::now(),
+            options,
+            result,
+        }
+    }
+}
+
+//gzipで圧縮したJSONとして保存する
+pub fn to_bytes(snapshot: &Snapshot) -> Result<Vec<u8>, Error> {
+    let json = serde_json::to_vec(snapshot).map_err(|e| Error::ExportFailed(e.to_string()))?;
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| Error::ExportFailed(e.to_string()))
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, Error> {
+    let mut json = vec![];
+    GzDecoder::new(bytes)
+        .read_to_end(&mut json)
+        .map_err(|e| Error::ImportFailed(e.to_string()))?;
+    let snapshot: Snapshot = serde_json::from_slice(&json).map_err(|e| Error::ImportFailed(e.to_string()))?;
+    if snapshot.version > VERSION {
+        return Err(Error::ImportFailed(format!("unsupported snapshot version {}", snapshot.version)));
+    }
+    Ok(snapshot)
+}
@@ -0,0 +1,127 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use iced::{theme::Palette, Color, Theme};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, filetype::FileType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThemeChoice {
+    //OSのライト/ダーク設定に合わせる
+    System,
+    BuiltIn(Theme),
+    //設定ファイルの [palette] で定義した配色
+    Custom,
+}
+
+impl ThemeChoice {
+    pub fn all() -> Vec<Self> {
+        std::iter::once(Self::System)
+            .chain(Theme::ALL.iter().cloned().map(Self::BuiltIn))
+            .chain(std::iter::once(Self::Custom))
+            .collect()
+    }
+
+    //設定ファイルに保存した名前から選ぶ。知らない名前ならダークにする
+    pub fn from_name(name: &str) -> Self {
+        Self::all()
+            .into_iter()
+            .find(|choice| choice.to_string() == name)
+            .unwrap_or(Self::BuiltIn(Theme::Dark))
+    }
+
+    pub fn resolve(&self, palette: &CustomPalette) -> Theme {
+        match self {
+            ThemeChoice::System => match dark_light::detect() {
+                dark_light::Mode::Light => Theme::Light,
+                _ => Theme::Dark,
+            },
+            ThemeChoice::BuiltIn(theme) => theme.clone(),
+            ThemeChoice::Custom => palette.to_theme().unwrap_or_else(|e| {
+                log::error!("{}", e);
+                Theme::Dark
+            }),
+        }
+    }
+}
+
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeChoice::System => "System".fmt(f),
+            ThemeChoice::BuiltIn(theme) => theme.fmt(f),
+            ThemeChoice::Custom => "Custom".fmt(f),
+        }
+    }
+}
+
+//色は "#rrggbb" で書く
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomPalette {
+    pub name: String,
+    pub background: String,
+    pub text: String,
+    pub primary: String,
+    pub success: String,
+    pub danger: String,
+}
+
+impl Default for CustomPalette {
+    fn default() -> Self {
+        let palette = Palette::DARK;
+        Self {
+            name: "Custom".into(),
+            background: to_hex(palette.background),
+            text: to_hex(palette.text),
+            primary: to_hex(palette.primary),
+            success: to_hex(palette.success),
+            danger: to_hex(palette.danger),
+        }
+    }
+}
+
+impl CustomPalette {
+    pub fn to_theme(&self) -> Result<Theme, Error> {
+        Ok(Theme::custom(
+            self.name.clone(),
+            Palette {
+                background: parse_hex(&self.background)?,
+                text: parse_hex(&self.text)?,
+                primary: parse_hex(&self.primary)?,
+                success: parse_hex(&self.success)?,
+                danger: parse_hex(&self.danger)?,
+            },
+        ))
+    }
+}
+
+pub fn to_hex(color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn parse_hex(text: &str) -> Result<Color, Error> {
+    let invalid = || Error::InvalidConfig(format!("invalid colour: {}", text));
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |range| u8::from_str_radix(&hex[range], 16).map_err(|_| invalid());
+    Ok(Color::from_rgb8(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+//分類ごとの色の上書きを反映する。読めない色は既定の色のままにする
+pub fn apply_colors(colors: &BTreeMap<FileType, String>) {
+    let colors = colors
+        .iter()
+        .filter_map(|(file_type, hex)| match parse_hex(hex) {
+            Ok(color) => Some((*file_type, color)),
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        })
+        .collect();
+    crate::filetype::set_colors(colors);
+}
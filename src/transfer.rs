@@ -0,0 +1,394 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_recursion::async_recursion;
+use iced::futures::{
+    channel::{mpsc, oneshot},
+    SinkExt,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{error::Error, file};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictAction {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+type ConflictAnswer = (ConflictAction, bool);
+
+//衝突ダイアログの回答を転送タスクへ返すためのハンドル
+#[derive(Clone)]
+pub struct ConflictResponder(Arc<Mutex<Option<oneshot::Sender<ConflictAnswer>>>>);
+
+impl ConflictResponder {
+    pub fn respond(&self, action: ConflictAction, apply_to_all: bool) {
+        if let Some(sender) = self.0.lock().unwrap().take() {
+            let _ = sender.send((action, apply_to_all));
+        }
+    }
+}
+
+impl fmt::Debug for ConflictResponder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ConflictResponder")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TransferEvent {
+    Progress {
+        copied: u64,
+        total: u64,
+        current: PathBuf,
+    },
+    Conflict(PathBuf, ConflictResponder),
+}
+
+pub struct TransferState {
+    pub kind: TransferKind,
+    pub copied: u64,
+    pub total: u64,
+    pub current: PathBuf,
+    pub conflict: Option<(PathBuf, ConflictResponder)>,
+    pub apply_to_all: bool,
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl TransferState {
+    pub fn new(kind: TransferKind) -> Self {
+        Self {
+            kind,
+            copied: 0,
+            total: 0,
+            current: PathBuf::new(),
+            conflict: None,
+            apply_to_all: false,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.copied as f32 / self.total as f32
+        }
+    }
+}
+
+struct Progress {
+    sender: mpsc::Sender<TransferEvent>,
+    cancel: Arc<AtomicBool>,
+    copied: u64,
+    total: u64,
+}
+
+impl Progress {
+    async fn advance(&mut self, bytes: u64, current: &Path) -> Result<(), Error> {
+        self.copied += bytes;
+        let _ = self
+            .sender
+            .send(TransferEvent::Progress {
+                copied: self.copied,
+                total: self.total,
+                current: current.to_path_buf(),
+            })
+            .await;
+        if self.cancel.load(Ordering::Relaxed) {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub async fn run(
+    kind: TransferKind,
+    sources: Vec<PathBuf>,
+    destination: PathBuf,
+    sender: mpsc::Sender<TransferEvent>,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), Error> {
+    for source in &sources {
+        if destination.starts_with(source) {
+            return Err(Error::InvalidDestination(destination));
+        }
+    }
+
+    let mut total = 0;
+    for source in &sources {
+        total += entry_size(source).await;
+    }
+    let mut progress = Progress {
+        sender,
+        cancel,
+        copied: 0,
+        total,
+    };
+
+    let mut apply_to_all = None;
+    for source in sources {
+        let Some(name) = source.file_name() else {
+            continue;
+        };
+        let mut target = destination.join(name);
+        let mut replace = false;
+
+        if tokio::fs::symlink_metadata(&target).await.is_ok() {
+            let action = match apply_to_all {
+                Some(action) => action,
+                None => {
+                    let (action, all) = ask_conflict(&mut progress.sender, &target).await;
+                    if all {
+                        apply_to_all = Some(action);
+                    }
+                    action
+                }
+            };
+            match action {
+                ConflictAction::Skip => {
+                    let size = entry_size(&source).await;
+                    progress.advance(size, &source).await?;
+                    continue;
+                }
+                ConflictAction::Overwrite => {
+                    //同じ場所への上書きは何もしない
+                    if target == source {
+                        let size = entry_size(&source).await;
+                        progress.advance(size, &source).await?;
+                        continue;
+                    }
+                    //上書き先を消すと転送元も消えてしまう
+                    if source.starts_with(&target) {
+                        return Err(Error::InvalidDestination(target));
+                    }
+                    replace = true;
+                }
+                ConflictAction::Rename => target = file::unique_path(&target),
+            }
+        }
+
+        if replace {
+            //転送が終わるまで既存のファイルは残しておき、成功してから置き換える
+            let temp = temp_path(&target);
+            let result = transfer_entry(kind, &source, &temp, &mut progress).await;
+            //転送元を消せなかっただけなら一時ファイルは完全なので、置き換えを済ませてから報告する
+            if let Err(e) = &result {
+                if !matches!(e, Error::SourceNotRemoved(_)) {
+                    restore_temp(kind, &source, &temp).await;
+                    return result;
+                }
+            }
+            replace_with_temp(&temp, &target).await?;
+            result?;
+        } else {
+            transfer_entry(kind, &source, &target, &mut progress).await?;
+        }
+    }
+    Ok(())
+}
+
+//置き換えに失敗しても転送した内容は一時ファイルに残っているので、その場所を伝える
+async fn replace_with_temp(temp: &Path, target: &Path) -> Result<(), Error> {
+    if remove_entry(target).await.is_err() || tokio::fs::rename(temp, target).await.is_err() {
+        return Err(Error::ReplaceFailed(temp.to_path_buf()));
+    }
+    Ok(())
+}
+
+async fn transfer_entry(
+    kind: TransferKind,
+    source: &Path,
+    target: &Path,
+    progress: &mut Progress,
+) -> Result<(), Error> {
+    match kind {
+        TransferKind::Copy => copy_new(source, target, progress).await,
+        TransferKind::Move => move_entry(source, target, progress).await,
+    }
+}
+
+//置き換え用の一時的な名前。上書き先と同じディレクトリに作るのでrenameで置き換えられる
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file::unique_path(&target.with_file_name(format!(".{}.filersmanager-tmp", name)))
+}
+
+//書きかけのコピーはcopy_newが片付けるので、ここで戻すのはrenameで移動し終えた一時ファイルだけ
+async fn restore_temp(kind: TransferKind, source: &Path, temp: &Path) {
+    if kind == TransferKind::Move
+        && tokio::fs::symlink_metadata(temp).await.is_ok()
+        && tokio::fs::symlink_metadata(source).await.is_err()
+    {
+        let _ = tokio::fs::rename(temp, source).await;
+    }
+}
+
+async fn ask_conflict(
+    sender: &mut mpsc::Sender<TransferEvent>,
+    target: &Path,
+) -> ConflictAnswer {
+    let (tx, rx) = oneshot::channel();
+    let responder = ConflictResponder(Arc::new(Mutex::new(Some(tx))));
+    let _ = sender
+        .send(TransferEvent::Conflict(target.to_path_buf(), responder))
+        .await;
+    rx.await.unwrap_or((ConflictAction::Skip, false))
+}
+
+async fn move_entry(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), Error> {
+    match tokio::fs::rename(source, target).await {
+        Ok(_) => {
+            let size = entry_size(target).await;
+            progress.advance(size, source).await
+        }
+        //別のファイルシステムへはrenameできないのでコピーしてから削除する
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_new(source, target, progress).await?;
+            remove_entry(source)
+                .await
+                .map_err(|_| Error::SourceNotRemoved(source.to_path_buf()))
+        }
+        Err(e) => Err(Error::AsyncTokioIoError(e.kind())),
+    }
+}
+
+//中断や失敗で途中までしかコピーできなかったときは、作りかけのものを残さない
+async fn copy_new(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), Error> {
+    let result = copy_entry(source, target, progress).await;
+    if result.is_err() && tokio::fs::symlink_metadata(target).await.is_ok() {
+        let _ = remove_entry(target).await;
+    }
+    result
+}
+
+#[async_recursion]
+async fn copy_entry(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), Error> {
+    let meta = tokio::fs::symlink_metadata(source)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+
+    if meta.is_symlink() {
+        copy_symlink(source, target).await
+    } else if meta.is_dir() {
+        tokio::fs::create_dir(target)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        let mut entries = tokio::fs::read_dir(source)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?
+        {
+            copy_entry(&entry.path(), &target.join(entry.file_name()), progress).await?;
+        }
+        let _ = tokio::fs::set_permissions(target, meta.permissions()).await;
+        Ok(())
+    } else {
+        copy_file(source, target, progress).await?;
+        let _ = tokio::fs::set_permissions(target, meta.permissions()).await;
+        Ok(())
+    }
+}
+
+async fn copy_file(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), Error> {
+    let mut reader = tokio::fs::File::open(source)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    let mut writer = tokio::fs::File::create(target)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .await
+            .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+        if let Err(e) = progress.advance(read as u64, source).await {
+            //中断時は書きかけのファイルを残さない
+            drop(writer);
+            let _ = tokio::fs::remove_file(target).await;
+            return Err(e);
+        }
+    }
+    writer
+        .flush()
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))
+}
+
+#[cfg(unix)]
+async fn copy_symlink(source: &Path, target: &Path) -> Result<(), Error> {
+    let link = tokio::fs::read_link(source)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    tokio::fs::symlink(link, target)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))
+}
+
+#[cfg(not(unix))]
+async fn copy_symlink(source: &Path, target: &Path) -> Result<(), Error> {
+    tokio::fs::copy(source, target)
+        .await
+        .map(|_| ())
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))
+}
+
+async fn remove_entry(path: &Path) -> Result<(), Error> {
+    let meta = tokio::fs::symlink_metadata(path)
+        .await
+        .map_err(|e| Error::AsyncTokioIoError(e.kind()))?;
+    if meta.is_dir() {
+        tokio::fs::remove_dir_all(path).await
+    } else {
+        tokio::fs::remove_file(path).await
+    }
+    .map_err(|e| Error::AsyncTokioIoError(e.kind()))
+}
+
+#[async_recursion]
+async fn entry_size(path: &Path) -> u64 {
+    let Ok(meta) = tokio::fs::symlink_metadata(path).await else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    let mut size = 0;
+    if let Ok(mut entries) = tokio::fs::read_dir(path).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            size += entry_size(&entry.path()).await;
+        }
+    }
+    size
+}
@@ -0,0 +1,102 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+const IEC_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const SI_UNITS: &[&str] = &["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitStyle {
+    //1024単位(KiB, MiB, ...)
+    #[default]
+    Iec,
+    //1000単位(kB, MB, ...)
+    Si,
+    Bytes,
+}
+
+impl UnitStyle {
+    pub const ALL: &'static [Self] = &[Self::Iec, Self::Si, Self::Bytes];
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Si,
+            2 => Self::Bytes,
+            _ => Self::Iec,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            UnitStyle::Iec => 0,
+            UnitStyle::Si => 1,
+            UnitStyle::Bytes => 2,
+        }
+    }
+}
+
+impl Display for UnitStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitStyle::Iec => tr("units.iec"),
+            UnitStyle::Si => tr("units.si"),
+            UnitStyle::Bytes => tr("units.bytes"),
+        }
+        .fmt(f)
+    }
+}
+
+//表示設定はテーブル・キャンバス・レポートのどこからでも参照するのでプロセス全体で共有する
+static STYLE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_unit_style(style: UnitStyle) {
+    STYLE.store(style.to_u8(), Ordering::Relaxed);
+}
+
+pub fn unit_style() -> UnitStyle {
+    UnitStyle::from_u8(STYLE.load(Ordering::Relaxed))
+}
+
+//現在の表示設定でサイズを文字列にする
+pub fn format_size(bytes: u64) -> String {
+    format_size_as(bytes, unit_style())
+}
+
+pub fn format_size_as(bytes: u64, style: UnitStyle) -> String {
+    let (base, units) = match style {
+        UnitStyle::Iec => (1024, IEC_UNITS),
+        UnitStyle::Si => (1000, SI_UNITS),
+        UnitStyle::Bytes => return format!("{} B", group_digits(bytes)),
+    };
+    if bytes < base {
+        return format!("{} B", bytes);
+    }
+    //浮動小数点を使わず小数第2位で四捨五入する。繰り上がりで基数に達したら次の単位にする
+    let mut divisor = base as u128;
+    for unit in &units[1..] {
+        let hundredths = (bytes as u128 * 100 + divisor / 2) / divisor;
+        if hundredths < base as u128 * 100 || *unit == units[units.len() - 1] {
+            return format!("{}.{:02} {}", hundredths / 100, hundredths % 100, unit);
+        }
+        divisor *= base as u128;
+    }
+    unreachable!()
+}
+
+fn group_digits(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
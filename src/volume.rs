@@ -0,0 +1,185 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+//容量を持たない仮想ファイルシステムは一覧に出さない
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "mqueue", "debugfs", "tracefs", "securityfs", "pstore",
+    "bpf", "configfs", "fusectl", "hugetlbfs", "autofs", "binfmt_misc", "rpc_pipefs", "nsfs", "squashfs", "efivarfs",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    pub mount: Mount,
+    pub total: u64,
+    pub free: u64,
+}
+
+impl Volume {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used() as f32 / self.total as f32
+        }
+    }
+}
+
+//書式: "36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw"
+pub fn parse_mountinfo(text: &str) -> Vec<Mount> {
+    text.lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let mount_point = left.split(' ').nth(4)?;
+            let mut right = right.split(' ');
+            Some(Mount {
+                mount_point: PathBuf::from(unescape(mount_point)),
+                fs_type: right.next()?.to_string(),
+                source: unescape(right.next().unwrap_or_default()),
+            })
+        })
+        .collect()
+}
+
+//空白などは\040のような8進数でエスケープされている
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let octal = bytes.get(index + 1..index + 4).and_then(|digits| {
+            let digits = std::str::from_utf8(digits).ok()?;
+            u8::from_str_radix(digits, 8).ok()
+        });
+        match (bytes[index], octal) {
+            (b'\\', Some(byte)) => {
+                unescaped.push(byte);
+                index += 4;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+#[cfg(target_os = "linux")]
+pub fn mounts() -> Vec<Mount> {
+    std::fs::read_to_string("/proc/self/mountinfo")
+        .map(|text| parse_mountinfo(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn mounts() -> Vec<Mount> {
+    vec![Mount {
+        mount_point: PathBuf::from("/"),
+        fs_type: String::new(),
+        source: String::new(),
+    }]
+}
+
+//ドライブ文字ごとに一覧にする。ビットiがドライブ'A'+iを表す
+#[cfg(windows)]
+pub fn mounts() -> Vec<Mount> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLogicalDrives() -> u32;
+    }
+    let drives = unsafe { GetLogicalDrives() };
+    (0..26u8)
+        .filter(|index| drives & (1 << index) != 0)
+        .map(|index| Mount {
+            mount_point: PathBuf::from(format!("{}:\\", (b'A' + index) as char)),
+            fs_type: String::new(),
+            source: String::new(),
+        })
+        .collect()
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn mounts() -> Vec<Mount> {
+    vec![]
+}
+
+#[cfg(unix)]
+pub fn statvfs(path: &Path) -> Option<(u64, u64)> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    //利用者が実際に使える空き容量(f_bavail)を空きとして扱う
+    Some((stat.f_blocks as u64 * block, stat.f_bavail as u64 * block))
+}
+
+//メディアの入っていないドライブなどは失敗するので一覧から外れる
+#[cfg(windows)]
+pub fn statvfs(path: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_available: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut free, mut total, mut total_free) = (0u64, 0u64, 0u64);
+    if unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut free, &mut total, &mut total_free) } == 0 {
+        return None;
+    }
+    Some((total, free))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn statvfs(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+//statvfsは応答しないネットワークドライブで止まることがあるので別スレッドで呼び出し、待つ時間にも上限を設ける
+const STATVFS_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub async fn volumes() -> Vec<Volume> {
+    let mut pending = vec![];
+    for mount in mounts() {
+        if PSEUDO_FILESYSTEMS.contains(&mount.fs_type.as_str())
+            || pending.iter().any(|(pending, _): &(Mount, _)| pending.mount_point == mount.mount_point)
+        {
+            continue;
+        }
+        let mount_point = mount.mount_point.clone();
+        pending.push((mount, tokio::task::spawn_blocking(move || statvfs(&mount_point))));
+    }
+
+    let deadline = tokio::time::Instant::now() + STATVFS_TIMEOUT;
+    let mut volumes = vec![];
+    for (mount, handle) in pending {
+        match tokio::time::timeout_at(deadline, handle).await {
+            Ok(Ok(Some((total, free)))) if total > 0 => volumes.push(Volume { mount, total, free }),
+            Ok(_) => {}
+            Err(_) => log::warn!("statvfs timed out: {}", mount.mount_point.display()),
+        }
+    }
+    volumes
+}
@@ -1,12 +1,16 @@
-use std::{fmt::Display, fs::Permissions, path::PathBuf, time::SystemTime};
+use std::{cmp::Ordering, fmt::Display, path::{Path, PathBuf}, time::SystemTime};
 
 use chrono::{DateTime, Local};
 use iced::{
-    widget::{button, container, pick_list, row, scrollable, text, Space,}, Element, Font, Length, Renderer, Theme
+    widget::{button, checkbox, column, container, progress_bar, row, scrollable, text, text_input, Space,}, Element, Length, Renderer, Theme
 };
 use iced_table::table;
+use serde::{Deserialize, Serialize};
 
-use crate::{file::EntryType, icon::{file_icon, folder_icon}, Message};
+pub mod treemap;
+pub mod sunburst;
+
+use crate::{cli::SortKey, file::EntryType, filetype::FileType, i18n::tr, icon::{file_icon, folder_icon}, Message};
 
 pub struct TableState {
     pub columns: Vec<TableColumn>,
@@ -24,7 +28,9 @@ impl TableState {
                     TableColumn::new(ColumnKind::Index),
                     TableColumn::new(ColumnKind::FileName),
                     TableColumn::new(ColumnKind::Size),
+                    TableColumn::new(ColumnKind::Percent),
                     TableColumn::new(ColumnKind::ModifiedTime),
+                    TableColumn::new(ColumnKind::Rename),
                     TableColumn::new(ColumnKind::Delete),
                 ],
                 rows: item,
@@ -38,7 +44,9 @@ impl TableState {
                     TableColumn::new(ColumnKind::Index),
                     TableColumn::new(ColumnKind::FileName),
                     TableColumn::new(ColumnKind::Size),
+                    TableColumn::new(ColumnKind::Percent),
                     TableColumn::new(ColumnKind::ModifiedTime),
+                    TableColumn::new(ColumnKind::Rename),
                     TableColumn::new(ColumnKind::Delete),
                 ],
                 rows: vec![],
@@ -51,9 +59,106 @@ impl TableState {
 
     pub fn set_rows(&mut self, rows: Vec<FileTableRow>) {
         self.rows = rows;
+        self.update_shares();
+    }
+
+    //親フォルダ合計に対する割合。行が増減するたびに計算し直す
+    fn update_shares(&mut self) {
+        let total: u64 = self.rows.iter().map(|row| row.bytes).sum();
+        for row in self.rows.iter_mut() {
+            row.share = if total == 0 { 0.0 } else { (row.bytes as f64 / total as f64) as f32 };
+        }
+    }
+
+    pub fn set_size_bars(&mut self, show: bool) {
+        self.columns.iter_mut().for_each(|column| column.show_bar = show);
+    }
+
+    //ツリー全体から集めた行はファイル名だけでは区別できないのでパスを表示する
+    pub fn set_full_path(&mut self, show: bool) {
+        self.columns.iter_mut().for_each(|column| column.show_path = show);
+    }
+
+    //スナップショット表示中は名前変更と削除のボタンを無効にする
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.columns.iter_mut().for_each(|column| column.read_only = read_only);
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.rows.iter().position(|row| row.selected)
+    }
+
+    pub fn renaming_index(&self) -> Option<usize> {
+        self.rows.iter().position(|row| row.rename_value.is_some())
+    }
+
+    //表示中の並び順を保ったまま行を挿入する
+    pub fn insert_sorted(&mut self, row: FileTableRow, sort: SortKey) {
+        let index = self.rows.partition_point(|r| sort.compare(r.sort_key(), row.sort_key()) != Ordering::Greater);
+        self.rows.insert(index, row);
+        self.update_shares();
+    }
+
+    pub fn remove_row(&mut self, index: usize) -> FileTableRow {
+        let row = self.rows.remove(index);
+        self.update_shares();
+        row
+    }
+
+    pub fn cancel_rename(&mut self) {
+        self.rows.iter_mut().for_each(|row| row.rename_value = None);
+    }
+
+    //設定の並び順で表示する列だけを並べる
+    pub fn set_columns(&mut self, settings: &[ColumnSetting]) {
+        let show_bar = self.columns.first().is_none_or(|column| column.show_bar);
+        let show_path = self.columns.first().is_some_and(|column| column.show_path);
+        let read_only = self.columns.first().is_some_and(|column| column.read_only);
+        self.columns = settings
+            .iter()
+            .filter(|setting| setting.visible)
+            .map(|setting| TableColumn {
+                width: setting.width,
+                show_bar,
+                show_path,
+                read_only,
+                ..TableColumn::new(setting.kind)
+            })
+            .collect();
+    }
+}
+
+//設定ファイルに保存する列の幅・表示・並び順
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSetting {
+    pub kind: ColumnKind,
+    pub width: f32,
+    #[serde(default = "visible")]
+    pub visible: bool,
+}
+
+fn visible() -> bool {
+    true
+}
+
+impl ColumnSetting {
+    pub fn new(kind: ColumnKind) -> Self {
+        Self {
+            kind,
+            width: TableColumn::new(kind).width,
+            visible: true,
+        }
     }
 }
 
+pub fn rename_input_id() -> text_input::Id {
+    text_input::Id::new("rename_input")
+}
+
+pub fn new_entry_input_id() -> text_input::Id {
+    text_input::Id::new("new_entry_input")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Category {
     FileName,
@@ -62,10 +167,6 @@ pub enum Category {
     Perm,
 }
 
-impl Category {
-    const ALL: &'static [Self] = &[Self::FileName, Self::Size, Self::DateModified, Self::Perm];
-}
-
 impl Display for Category {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -78,24 +179,82 @@ impl Display for Category {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Table,
+    Treemap,
+    Sunburst,
+    Types,
+    Age,
+    Duplicates,
+    Empty,
+    Junk,
+    Volumes,
+    Diff,
+}
+
+impl ViewMode {
+    pub const ALL: &'static [Self] = &[Self::Table, Self::Treemap, Self::Sunburst, Self::Types, Self::Age, Self::Duplicates, Self::Empty, Self::Junk, Self::Volumes, Self::Diff];
+}
+
+impl Display for ViewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViewMode::Table => tr("view.table"),
+            ViewMode::Treemap => tr("view.treemap"),
+            ViewMode::Sunburst => tr("view.sunburst"),
+            ViewMode::Types => tr("view.types"),
+            ViewMode::Age => tr("view.age"),
+            ViewMode::Duplicates => tr("view.duplicates"),
+            ViewMode::Empty => tr("view.empty"),
+            ViewMode::Junk => tr("view.junk"),
+            ViewMode::Volumes => tr("view.volumes"),
+            ViewMode::Diff => tr("view.diff"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ColumnKind {
     Index,
     FileName,
     Size,
+    Percent,
     ModifiedTime,
+    Rename,
     Delete,
 }
 
+impl ColumnKind {
+    pub const ALL: &'static [Self] = &[
+        Self::Index,
+        Self::FileName,
+        Self::Size,
+        Self::Percent,
+        Self::ModifiedTime,
+        Self::Rename,
+        Self::Delete,
+    ];
+}
+
 #[derive(Debug,Clone)]
 pub struct FileTableRow {
     filename: PathBuf,
+    bytes: u64,
     size: String,
     time:String,
+    timestamp:Option<SystemTime>,
     entry_type:EntryType,
+    share: f32,
+    pub selected: bool,
+    pub rename_value: Option<String>,
 }
 
 impl FileTableRow {
-    pub fn generate(filename: PathBuf, size: String,time:Option<SystemTime>) -> Self {
+    pub fn generate(filename: PathBuf, bytes: u64, size: String,time:Option<SystemTime>,is_dir:bool) -> Self {
+        let timestamp = time;
         let time = match time {
             Some(time) => {
                 let datetime :DateTime<Local>=  DateTime::from(time);
@@ -103,32 +262,61 @@ impl FileTableRow {
             },
             None => "".into(),
         };
-        let entry_type = EntryType::generate(&filename);
+        //読み込んだスナップショットのパスは手元にないことが多いので、ディスクではなくツリーの情報で決める
+        let entry_type = if is_dir { EntryType::Dir } else { EntryType::File };
 
         Self {
-            filename: filename,
-            size: size,
-            time:time,
-            entry_type:entry_type,
+            filename,
+            bytes,
+            size,
+            time,
+            timestamp,
+            entry_type,
+            share: 0.0,
+            selected: false,
+            rename_value: None,
         }
     }
 
-    pub fn get_filepath(&mut self)->PathBuf{
+    pub fn get_filepath(&self)->PathBuf{
         self.filename.clone()
     }
+
+    pub fn set_filepath(&mut self,path:PathBuf){
+        self.filename = path;
+    }
+
+    pub fn bytes(&self)->u64{
+        self.bytes
+    }
+
+    fn sort_key(&self)->(&Path,u64,Option<SystemTime>){
+        (&self.filename,self.bytes,self.timestamp)
+    }
+
+    pub fn share(&self)->f32{
+        self.share
+    }
+
+    pub fn file_name(&self)->String{
+        self.filename.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+    }
 }
 
-impl ToString for FileTableRow {
-    fn to_string(&self) -> String {
+impl Display for FileTableRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let filename=self.filename.file_name().unwrap().to_str().unwrap();
-        format!("{}\t{}\t{}",filename,&self.size,&self.time)
+        write!(f,"{}\t{}\t{}",filename,&self.size,&self.time)
     }
 }
 
 pub struct TableColumn {
-    kind: ColumnKind,
+    pub kind: ColumnKind,
     pub width: f32,
     pub resize_offset: Option<f32>,
+    pub show_bar: bool,
+    pub show_path: bool,
+    pub read_only: bool,
 }
 
 impl TableColumn {
@@ -137,7 +325,9 @@ impl TableColumn {
             ColumnKind::Index => 60.0,
             ColumnKind::FileName => 600.0,
             ColumnKind::Size => 90.0,
+            ColumnKind::Percent => 70.0,
             ColumnKind::ModifiedTime => 140.0,
+            ColumnKind::Rename => 100.0,
             ColumnKind::Delete => 100.0,
         };
 
@@ -145,6 +335,9 @@ impl TableColumn {
             kind,
             width,
             resize_offset: None,
+            show_bar: true,
+            show_path: false,
+            read_only: false,
         }
     }
 }
@@ -154,11 +347,13 @@ impl<'a> table::Column<'a, Message, Theme, Renderer> for TableColumn {
 
     fn header(&'a self, _col_index: usize) -> Element<'a, Message, Theme, Renderer> {
         let content = match self.kind {
-            ColumnKind::Index => "Index",
-            ColumnKind::FileName => "Name",
-            ColumnKind::Size => "Size",
-            ColumnKind::ModifiedTime => "Access Time",
-            ColumnKind::Delete => "Delete",
+            ColumnKind::Index => tr("column.index"),
+            ColumnKind::FileName => tr("column.name"),
+            ColumnKind::Size => tr("column.size"),
+            ColumnKind::Percent => "%",
+            ColumnKind::ModifiedTime => tr("column.modified"),
+            ColumnKind::Rename => tr("column.rename"),
+            ColumnKind::Delete => tr("column.delete"),
         };
 
         container(text(content)).height(24).center_y().into()
@@ -171,18 +366,51 @@ impl<'a> table::Column<'a, Message, Theme, Renderer> for TableColumn {
         row: &'a Self::Row,
     ) -> Element<'a, Message, Theme, Renderer> {
         let content: Element<_> = match self.kind {
-            ColumnKind::Index => text(row_index).into(),
+            ColumnKind::Index => checkbox(row_index.to_string(), row.selected)
+                .on_toggle(move |checked| Message::Select(row_index, checked))
+                .into(),
             ColumnKind::FileName => {
                 let icon = match row.entry_type {
                     EntryType::File => file_icon(),
                     EntryType::Dir => folder_icon(),
                 };
 
-                row!(icon,Space::with_width(Length::Fixed(10.)),text(&row.filename.file_name().unwrap().to_str().unwrap()),).into()
+                let name: Element<_> = match &row.rename_value {
+                    Some(value) => text_input("", value)
+                        .id(rename_input_id())
+                        .on_input(Message::RenameInput)
+                        .on_submit(Message::RenameSubmit)
+                        .into(),
+                    None => {
+                        let name = if self.show_path {
+                            text(row.filename.display())
+                        } else {
+                            text(row.filename.file_name().unwrap().to_str().unwrap())
+                        };
+                        //ファイルはグラフと同じ分類の色で表示する
+                        match row.entry_type {
+                            EntryType::File => name.style(FileType::from_name(&row.file_name()).color()).into(),
+                            EntryType::Dir => name.into(),
+                        }
+                    }
+                };
+
+                row!(icon,Space::with_width(Length::Fixed(10.)),name,).into()
             },
+            ColumnKind::Size if self.show_bar => column!(
+                text(&row.size),
+                progress_bar(0.0..=1.0, row.share).height(Length::Fixed(4.)),
+            )
+            .into(),
             ColumnKind::Size => text(&row.size).into(),
+            ColumnKind::Percent => text(format!("{:.1}%", row.share * 100.0)).into(),
             ColumnKind::ModifiedTime => text(&row.time).into(),
-            ColumnKind::Delete => button("delete").on_press(Message::Delete(row_index)).into(),
+            ColumnKind::Rename => button(tr("button.rename"))
+                .on_press_maybe((!self.read_only).then_some(Message::RenameStart(row_index)))
+                .into(),
+            ColumnKind::Delete => button(tr("button.delete"))
+                .on_press_maybe((!self.read_only).then_some(Message::Delete(row_index)))
+                .into(),
         };
 
         container(content)
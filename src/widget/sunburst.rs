@@ -0,0 +1,322 @@
+use std::{
+    cell::RefCell,
+    f32::consts::TAU,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use iced::{
+    mouse,
+    theme::Palette,
+    widget::canvas::{self, event, path::Builder, Event, Frame, Geometry, Stroke},
+    Color, Point, Rectangle, Renderer, Size, Theme, Vector,
+};
+
+use crate::{
+    analysis::percentage,
+    units::format_size,
+    filetype::FileType,
+    scan::{ScanNode, ScanResult},
+    widget::treemap::{age_color, draw_tooltip, ColorMode},
+    Message,
+};
+
+pub const DEPTHS: &[usize] = &[2, 3, 4, 5, 6, 8];
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub start: f32,
+    pub end: f32,
+    pub depth: usize,
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+    pub color: Color,
+}
+
+pub fn layout(node: &ScanNode, path: &Path, max_depth: usize, mode: ColorMode, now: SystemTime) -> Vec<Segment> {
+    let mut segments = vec![];
+    layout_children(node, path, 0.0, TAU, 0, max_depth, None, mode, now, &mut segments);
+    segments
+}
+
+#[allow(clippy::too_many_arguments)]
+fn layout_children(
+    node: &ScanNode,
+    path: &Path,
+    start: f32,
+    end: f32,
+    depth: usize,
+    max_depth: usize,
+    hue: Option<f32>,
+    mode: ColorMode,
+    now: SystemTime,
+    segments: &mut Vec<Segment>,
+) {
+    if depth >= max_depth || node.size == 0 {
+        return;
+    }
+    let mut angle = start;
+    let span = end - start;
+    let count = node.children.len().max(1) as f32;
+    for (index, child) in node.children.iter().enumerate() {
+        let sweep = span * (child.size as f64 / node.size as f64) as f32;
+        //0.5度未満の細い扇形は描画しない
+        if sweep < 0.5_f32.to_radians() {
+            angle += sweep;
+            continue;
+        }
+        let child_path = path.join(&child.name);
+        //最上位の枝ごとに色相を割り当て、深くなるほど明るくする
+        let hue = hue.unwrap_or(index as f32 / count * 360.0);
+        let color = if child.is_dir {
+            hsv(hue, 0.45, 0.55 + depth as f32 * 0.08)
+        } else {
+            match mode {
+                ColorMode::FileType => FileType::from_name(&child.name).color(),
+                ColorMode::Age => age_color(child.modified, now),
+            }
+        };
+        segments.push(Segment {
+            start: angle,
+            end: angle + sweep,
+            depth,
+            path: child_path.clone(),
+            size: child.size,
+            is_dir: child.is_dir,
+            color,
+        });
+        if child.is_dir {
+            layout_children(child, &child_path, angle, angle + sweep, depth + 1, max_depth, Some(hue), mode, now, segments);
+        }
+        angle += sweep;
+    }
+}
+
+fn hsv(hue: f32, saturation: f32, value: f32) -> Color {
+    let value = value.min(1.0);
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - c;
+    let (r, g, b) = match (hue % 360.0) as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    Color::from_rgb(r + m, g + m, b + m)
+}
+
+struct Rings {
+    center: Point,
+    hole: f32,
+    ring: f32,
+}
+
+impl Rings {
+    fn new(bounds: Size, max_depth: usize) -> Self {
+        let radius = bounds.width.min(bounds.height) / 2.0 - 4.0;
+        let hole = radius * 0.18;
+        Self {
+            center: Point::new(bounds.width / 2.0, bounds.height / 2.0),
+            hole,
+            ring: (radius - hole).max(0.0) / max_depth as f32,
+        }
+    }
+
+    fn radii(&self, depth: usize) -> (f32, f32) {
+        let inner = self.hole + self.ring * depth as f32;
+        (inner, inner + self.ring)
+    }
+
+    //カーソル位置をリングの深さと角度に変換する
+    fn locate(&self, point: Point) -> Option<(Option<usize>, f32)> {
+        let offset = point - self.center;
+        let distance = (offset.x * offset.x + offset.y * offset.y).sqrt();
+        let angle = offset.y.atan2(offset.x).rem_euclid(TAU);
+        if distance < self.hole {
+            Some((None, angle))
+        } else if self.ring > 0.0 {
+            Some((Some(((distance - self.hole) / self.ring) as usize), angle))
+        } else {
+            None
+        }
+    }
+}
+
+enum Hit<'a> {
+    Center,
+    Segment(&'a Segment),
+}
+
+pub struct Sunburst<'a> {
+    pub result: &'a ScanResult,
+    pub dir: &'a Path,
+    pub max_depth: usize,
+    pub mode: ColorMode,
+    pub selected: Vec<PathBuf>,
+}
+
+type LayoutKey = (PathBuf, usize, ColorMode, SystemTime, u64, Vec<PathBuf>);
+
+#[derive(Default)]
+pub struct SunburstState {
+    cache: canvas::Cache,
+    key: RefCell<Option<LayoutKey>>,
+    segments: RefCell<Vec<Segment>>,
+    //中心の穴は背景色で塗るので、テーマが変わったら描き直す
+    palette: RefCell<Option<Palette>>,
+}
+
+impl Sunburst<'_> {
+    fn segments<'s>(&self, state: &'s SunburstState) -> std::cell::Ref<'s, Vec<Segment>> {
+        let key = Some((
+            self.dir.to_path_buf(),
+            self.max_depth,
+            self.mode,
+            self.result.scanned_at,
+            self.result.revision,
+            self.selected.clone(),
+        ));
+        if *state.key.borrow() != key {
+            let segments = match self.result.node(self.dir) {
+                Some(node) => layout(node, self.dir, self.max_depth, self.mode, SystemTime::now()),
+                None => vec![],
+            };
+            *state.segments.borrow_mut() = segments;
+            *state.key.borrow_mut() = key;
+            state.cache.clear();
+        }
+        state.segments.borrow()
+    }
+
+    fn hit<'s>(&self, segments: &'s [Segment], bounds: Rectangle, point: Point) -> Option<Hit<'s>> {
+        let rings = Rings::new(bounds.size(), self.max_depth);
+        match rings.locate(point)? {
+            (None, _) => Some(Hit::Center),
+            (Some(depth), angle) => segments
+                .iter()
+                .find(|segment| segment.depth == depth && segment.start <= angle && angle < segment.end)
+                .map(Hit::Segment),
+        }
+    }
+}
+
+impl canvas::Program<Message> for Sunburst<'_> {
+    type State = SunburstState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Some(position) = cursor.position_in(bounds) else {
+            return (event::Status::Ignored, None);
+        };
+        let parent = self
+            .dir
+            .parent()
+            .filter(|parent| parent.starts_with(&self.result.root))
+            .map(|parent| Message::OpenDir(parent.to_path_buf()));
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let segments = self.segments(state);
+                let message = match self.hit(&segments, bounds, position) {
+                    //中央の円をクリックすると一つ上の階層へ戻る
+                    Some(Hit::Center) => parent,
+                    Some(Hit::Segment(segment)) if segment.is_dir => Some(Message::OpenDir(segment.path.clone())),
+                    //表示中フォルダ直下のファイルはテーブルの選択と連動させる
+                    Some(Hit::Segment(segment)) if segment.depth == 0 => Some(Message::SelectPath(segment.path.clone())),
+                    _ => None,
+                };
+                (event::Status::Captured, message)
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => (event::Status::Captured, parent),
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => (event::Status::Captured, None),
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let segments = self.segments(state);
+        let rings = Rings::new(bounds.size(), self.max_depth);
+        if state.palette.replace(Some(theme.palette())) != Some(theme.palette()) {
+            state.cache.clear();
+        }
+        let background = theme.palette().background;
+        let base = state.cache.draw(renderer, bounds.size(), |frame| {
+            let hole = canvas::Path::circle(rings.center, rings.hole);
+            frame.fill(&hole, theme.extended_palette().background.strong.color);
+            for segment in segments.iter() {
+                let path = sector(&rings, segment);
+                frame.fill(&path, segment.color);
+                frame.stroke(&path, Stroke::default().with_color(background).with_width(1.0));
+                if segment.depth == 0 && self.selected.contains(&segment.path) {
+                    frame.stroke(&path, Stroke::default().with_color(Color::WHITE).with_width(2.5));
+                }
+            }
+        });
+
+        let mut overlay = Frame::new(renderer, bounds.size());
+        if let Some(position) = cursor.position_in(bounds) {
+            let total = self.result.node(self.dir).map(|node| node.size).unwrap_or(0);
+            let label = match self.hit(&segments, bounds, position) {
+                Some(Hit::Segment(segment)) => {
+                    let path = sector(&rings, segment);
+                    overlay.stroke(&path, Stroke::default().with_color(Color::WHITE).with_width(2.0));
+                    Some(format!(
+                        "{}\n{} ({:.1}%)",
+                        segment.path.strip_prefix(&self.result.root).unwrap_or(&segment.path).display(),
+                        format_size(segment.size),
+                        percentage(segment.size, total)
+                    ))
+                }
+                Some(Hit::Center) => Some(format!("{}\n{}", self.dir.display(), format_size(total))),
+                None => None,
+            };
+            if let Some(label) = label {
+                draw_tooltip(&mut overlay, theme, position, bounds.size(), label);
+            }
+        }
+        vec![base, overlay.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, _state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+//扇形を外周と内周の折れ線で近似する
+fn sector(rings: &Rings, segment: &Segment) -> canvas::Path {
+    let (inner, outer) = rings.radii(segment.depth);
+    let steps = (((segment.end - segment.start) * outer / 4.0) as usize).clamp(2, 256);
+    let point = |radius: f32, angle: f32| rings.center + Vector::new(radius * angle.cos(), radius * angle.sin());
+
+    let mut builder = Builder::new();
+    builder.move_to(point(outer, segment.start));
+    for step in 1..=steps {
+        let angle = segment.start + (segment.end - segment.start) * step as f32 / steps as f32;
+        builder.line_to(point(outer, angle));
+    }
+    for step in (0..=steps).rev() {
+        let angle = segment.start + (segment.end - segment.start) * step as f32 / steps as f32;
+        builder.line_to(point(inner, angle));
+    }
+    builder.close();
+    builder.build()
+}
@@ -0,0 +1,359 @@
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use iced::{
+    mouse,
+    widget::{
+        canvas::{self, event, Event, Frame, Geometry, Path as CanvasPath, Stroke, Text},
+        text::Shaping,
+    },
+    theme::Palette,
+    Color, Point, Rectangle, Renderer, Size, Theme,
+};
+
+use crate::{filetype::FileType, i18n::tr, scan::{ScanNode, ScanResult}, units::format_size, Message};
+
+const MAX_DEPTH: usize = 3;
+const PADDING: f32 = 2.0;
+const DAY: u64 = 60 * 60 * 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    FileType,
+    Age,
+}
+
+impl ColorMode {
+    pub const ALL: &'static [Self] = &[Self::FileType, Self::Age];
+}
+
+impl Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorMode::FileType => tr("color.file_type"),
+            ColorMode::Age => tr("color.age"),
+        }
+        .fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub rect: Rectangle,
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+    pub depth: usize,
+    pub color: Color,
+}
+
+//Bruls らの squarified treemap。sizes は降順に並んでいることを前提とする
+pub fn squarify(sizes: &[u64], rect: Rectangle) -> Vec<Rectangle> {
+    let total: u64 = sizes.iter().sum();
+    if total == 0 || rect.width <= 0.0 || rect.height <= 0.0 {
+        return vec![Rectangle::new(rect.position(), Size::ZERO); sizes.len()];
+    }
+    let scale = (rect.width as f64 * rect.height as f64) / total as f64;
+    let areas: Vec<f64> = sizes.iter().map(|size| *size as f64 * scale).collect();
+
+    let (mut x, mut y) = (rect.x as f64, rect.y as f64);
+    let (mut width, mut height) = (rect.width as f64, rect.height as f64);
+    let mut result = Vec::with_capacity(sizes.len());
+    let mut start = 0;
+    while start < areas.len() {
+        let side = width.min(height);
+        let mut end = start + 1;
+        let mut sum = areas[start];
+        let mut best = worst_ratio(&areas[start..end], sum, side);
+        while end < areas.len() {
+            let next = worst_ratio(&areas[start..end + 1], sum + areas[end], side);
+            if next > best {
+                break;
+            }
+            best = next;
+            sum += areas[end];
+            end += 1;
+        }
+
+        if width >= height {
+            let row_width = if height > 0.0 { sum / height } else { 0.0 };
+            let mut offset = y;
+            for area in &areas[start..end] {
+                let h = if row_width > 0.0 { area / row_width } else { 0.0 };
+                result.push(rectangle(x, offset, row_width, h));
+                offset += h;
+            }
+            x += row_width;
+            width -= row_width;
+        } else {
+            let row_height = if width > 0.0 { sum / width } else { 0.0 };
+            let mut offset = x;
+            for area in &areas[start..end] {
+                let w = if row_height > 0.0 { area / row_height } else { 0.0 };
+                result.push(rectangle(offset, y, w, row_height));
+                offset += w;
+            }
+            y += row_height;
+            height -= row_height;
+        }
+        start = end;
+    }
+    result
+}
+
+fn worst_ratio(row: &[f64], sum: f64, side: f64) -> f64 {
+    let max = row.iter().cloned().fold(0.0, f64::max);
+    let min = row.iter().cloned().fold(f64::INFINITY, f64::min);
+    if min <= 0.0 || sum <= 0.0 {
+        return f64::INFINITY;
+    }
+    let (sum2, side2) = (sum * sum, side * side);
+    (side2 * max / sum2).max(sum2 / (side2 * min))
+}
+
+fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Rectangle {
+    Rectangle::new(Point::new(x as f32, y as f32), Size::new(width as f32, height as f32))
+}
+
+pub fn age_color(modified: Option<SystemTime>, now: SystemTime) -> Color {
+    let days = modified
+        .and_then(|time| now.duration_since(time).ok())
+        .map(|age| age.as_secs() / DAY);
+    match days {
+        Some(0) => Color::from_rgb8(0x4c, 0xaf, 0x50),
+        Some(1..=7) => Color::from_rgb8(0x8b, 0xc3, 0x4a),
+        Some(8..=30) => Color::from_rgb8(0xcd, 0xdc, 0x39),
+        Some(31..=365) => Color::from_rgb8(0xff, 0x98, 0x00),
+        Some(_) => Color::from_rgb8(0xf4, 0x43, 0x36),
+        None => Color::from_rgb8(0x7f, 0x84, 0x8e),
+    }
+}
+
+pub fn layout(node: &ScanNode, path: &Path, bounds: Rectangle, mode: ColorMode, now: SystemTime) -> Vec<Tile> {
+    let mut tiles = vec![];
+    layout_children(node, path, bounds, 0, mode, now, &mut tiles);
+    tiles
+}
+
+fn layout_children(
+    node: &ScanNode,
+    path: &Path,
+    bounds: Rectangle,
+    depth: usize,
+    mode: ColorMode,
+    now: SystemTime,
+    tiles: &mut Vec<Tile>,
+) {
+    let children: Vec<&ScanNode> = node.children.iter().filter(|child| child.size > 0).collect();
+    let sizes: Vec<u64> = children.iter().map(|child| child.size).collect();
+    for (child, rect) in children.into_iter().zip(squarify(&sizes, bounds)) {
+        if rect.width < 1.0 || rect.height < 1.0 {
+            continue;
+        }
+        let child_path = path.join(&child.name);
+        let color = if child.is_dir {
+            Color::from_rgb8(0x3a, 0x3f, 0x4b)
+        } else {
+            match mode {
+                ColorMode::FileType => FileType::from_name(&child.name).color(),
+                ColorMode::Age => age_color(child.modified, now),
+            }
+        };
+        tiles.push(Tile {
+            rect,
+            path: child_path.clone(),
+            size: child.size,
+            is_dir: child.is_dir,
+            depth,
+            color,
+        });
+
+        let inner = Rectangle::new(
+            Point::new(rect.x + PADDING, rect.y + PADDING),
+            Size::new(rect.width - PADDING * 2.0, rect.height - PADDING * 2.0),
+        );
+        if child.is_dir && depth + 1 < MAX_DEPTH && inner.width > 4.0 && inner.height > 4.0 {
+            layout_children(child, &child_path, inner, depth + 1, mode, now, tiles);
+        }
+    }
+}
+
+//カーソル位置にある一番深いタイル
+pub fn tile_at(tiles: &[Tile], point: Point) -> Option<&Tile> {
+    tiles
+        .iter()
+        .filter(|tile| tile.rect.contains(point))
+        .max_by_key(|tile| tile.depth)
+}
+
+pub struct Treemap<'a> {
+    pub result: &'a ScanResult,
+    pub dir: &'a Path,
+    pub mode: ColorMode,
+}
+
+type LayoutKey = (PathBuf, ColorMode, SystemTime, u64, Size);
+
+#[derive(Default)]
+pub struct TreemapState {
+    cache: canvas::Cache,
+    key: RefCell<Option<LayoutKey>>,
+    tiles: RefCell<Vec<Tile>>,
+    //文字色はテーマから取るので、テーマが変わったら描き直す
+    palette: RefCell<Option<Palette>>,
+}
+
+impl Treemap<'_> {
+    //表示対象が変わったときだけタイルを計算し直す
+    fn tiles<'s>(&self, state: &'s TreemapState, bounds: Rectangle) -> std::cell::Ref<'s, Vec<Tile>> {
+        let key = Some((
+            self.dir.to_path_buf(),
+            self.mode,
+            self.result.scanned_at,
+            self.result.revision,
+            bounds.size(),
+        ));
+        if *state.key.borrow() != key {
+            let tiles = match self.result.node(self.dir) {
+                Some(node) => layout(
+                    node,
+                    self.dir,
+                    Rectangle::new(Point::ORIGIN, bounds.size()),
+                    self.mode,
+                    SystemTime::now(),
+                ),
+                None => vec![],
+            };
+            *state.tiles.borrow_mut() = tiles;
+            *state.key.borrow_mut() = key;
+            state.cache.clear();
+        }
+        state.tiles.borrow()
+    }
+}
+
+impl canvas::Program<Message> for Treemap<'_> {
+    type State = TreemapState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Some(position) = cursor.position_in(bounds) else {
+            return (event::Status::Ignored, None);
+        };
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let tiles = self.tiles(state, bounds);
+                let message = tile_at(&tiles, position)
+                    .filter(|tile| tile.is_dir)
+                    .map(|tile| Message::OpenDir(tile.path.clone()));
+                (event::Status::Captured, message)
+            }
+            //右クリックで一つ上の階層へ戻る
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                let message = self
+                    .dir
+                    .parent()
+                    .filter(|parent| parent.starts_with(&self.result.root))
+                    .map(|parent| Message::OpenDir(parent.to_path_buf()));
+                (event::Status::Captured, message)
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => (event::Status::Captured, None),
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let tiles = self.tiles(state, bounds);
+        if state.palette.replace(Some(theme.palette())) != Some(theme.palette()) {
+            state.cache.clear();
+        }
+        let text_color = theme.palette().text;
+        let base = state.cache.draw(renderer, bounds.size(), |frame| {
+            for tile in tiles.iter() {
+                frame.fill_rectangle(tile.rect.position(), tile.rect.size(), tile.color);
+                frame.stroke(
+                    &CanvasPath::rectangle(tile.rect.position(), tile.rect.size()),
+                    Stroke::default().with_color(Color::BLACK).with_width(0.5),
+                );
+                if tile.depth == 0 && tile.rect.width > 60.0 && tile.rect.height > 18.0 {
+                    let name = tile.path.file_name().unwrap_or_default().to_string_lossy();
+                    let max_chars = (tile.rect.width / 8.0) as usize;
+                    frame.fill_text(Text {
+                        content: name.chars().take(max_chars).collect(),
+                        position: Point::new(tile.rect.x + 3.0, tile.rect.y + 2.0),
+                        color: text_color,
+                        size: 12.0.into(),
+                        shaping: Shaping::Advanced,
+                        ..Text::default()
+                    });
+                }
+            }
+        });
+
+        let mut overlay = Frame::new(renderer, bounds.size());
+        if let Some(position) = cursor.position_in(bounds) {
+            if let Some(tile) = tile_at(&tiles, position) {
+                draw_highlight(&mut overlay, tile.rect);
+                let label = format!(
+                    "{}\n{}",
+                    tile.path.strip_prefix(&self.result.root).unwrap_or(&tile.path).display(),
+                    format_size(tile.size)
+                );
+                draw_tooltip(&mut overlay, theme, position, bounds.size(), label);
+            }
+        }
+        vec![base, overlay.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        match cursor.position_in(bounds) {
+            Some(position) if self.tiles(state, bounds).iter().any(|tile| tile.is_dir && tile.rect.contains(position)) => {
+                mouse::Interaction::Pointer
+            }
+            _ => mouse::Interaction::default(),
+        }
+    }
+}
+
+pub fn draw_highlight(frame: &mut Frame, rect: Rectangle) {
+    frame.stroke(
+        &CanvasPath::rectangle(rect.position(), rect.size()),
+        Stroke::default().with_color(Color::WHITE).with_width(2.0),
+    );
+}
+
+pub fn draw_tooltip(frame: &mut Frame, theme: &Theme, position: Point, bounds: Size, label: String) {
+    let lines = label.lines().count() as f32;
+    let width = label.lines().map(|line| line.chars().count()).max().unwrap_or(0) as f32 * 7.5 + 12.0;
+    let height = lines * 16.0 + 8.0;
+    //画面外にはみ出さないように位置を調整する
+    let x = (position.x + 12.0).min(bounds.width - width).max(0.0);
+    let y = (position.y + 12.0).min(bounds.height - height).max(0.0);
+
+    let palette = theme.extended_palette();
+    frame.fill_rectangle(Point::new(x, y), Size::new(width, height), palette.background.strong.color);
+    frame.fill_text(Text {
+        content: label,
+        position: Point::new(x + 6.0, y + 4.0),
+        color: palette.background.strong.text,
+        size: 13.0.into(),
+        shaping: Shaping::Advanced,
+        ..Text::default()
+    });
+}
@@ -9,19 +9,17 @@ where
     P:AsRef<Path>{
         let mut fsize = 0;
         if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries{
-                if let Ok(entry) = entry{
-                    if let Ok(meta) = entry.metadata(){
-                        if meta.is_file(){
-                            fsize+=meta.len();
-                        }else if meta.is_dir() {
-                            fsize+=serach_file(entry.path());
-                        }
+            for entry in entries.flatten(){
+                if let Ok(meta) = entry.metadata(){
+                    if meta.is_file(){
+                        fsize+=meta.len();
+                    }else if meta.is_dir() {
+                        fsize+=serach_file(entry.path());
                     }
                 }
             }
         }
-        return fsize;
+        fsize
     }
 
 // #[async_recursion]
@@ -49,15 +47,13 @@ where
 fn test_sort()->O{
     let mut hash = HashMap::new();
     if let Ok(entries) = fs::read_dir(DESKTOP_PATH){
-        for entry in entries{
-            if let Ok(entry) = entry{
-                if let Ok(meta) = entry.metadata() {
-                    if meta.is_file(){
-                        hash.insert(entry.path(),meta.len());
-                    }else if meta.is_dir(){
-                        let total_size = serach_file(&entry.path());
-                        hash.insert(entry.path(), total_size);
-                    }
+        for entry in entries.flatten(){
+            if let Ok(meta) = entry.metadata() {
+                if meta.is_file(){
+                    hash.insert(entry.path(),meta.len());
+                }else if meta.is_dir(){
+                    let total_size = serach_file(entry.path());
+                    hash.insert(entry.path(), total_size);
                 }
             }
         }
@@ -79,3 +75,992 @@ impl MExtension for u64 {
         else {Ordering::Less}
     }
 }
+
+fn temp_dir(name:&str)->PathBuf{
+    let dir = std::env::temp_dir().join(format!("filersmanager_{}_{}",name,std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_validate_new_name()->O{
+    let dir = temp_dir("rename");
+    let a = dir.join("a.txt");
+    fs::write(&a, "a")?;
+    fs::write(dir.join("b.txt"), "b")?;
+
+    assert_eq!(filersmanager::file::validate_new_name(&a, "c.txt").unwrap(),dir.join("c.txt"));
+    assert_eq!(filersmanager::file::validate_new_name(&a, " c.txt ").unwrap(),dir.join("c.txt"));
+    assert!(filersmanager::file::validate_new_name(&a, "b.txt").is_err());
+    assert!(filersmanager::file::validate_new_name(&a, "sub/c.txt").is_err());
+    assert!(filersmanager::file::validate_new_name(&a, "..").is_err());
+    assert!(filersmanager::file::validate_new_name(&a, " ").is_err());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_unique_path()->O{
+    let dir = temp_dir("unique");
+    let a = dir.join("a.txt");
+    fs::write(&a, "a")?;
+
+    assert_eq!(filersmanager::file::unique_path(&a),dir.join("a (1).txt"));
+    fs::write(dir.join("a (1).txt"), "a")?;
+    assert_eq!(filersmanager::file::unique_path(&a),dir.join("a (2).txt"));
+    assert_eq!(filersmanager::file::unique_path(&dir.join("sub")),dir.join("sub (1)"));
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_validate_entry_name()->O{
+    let dir = temp_dir("new_entry");
+    fs::create_dir(dir.join("exists"))?;
+
+    assert_eq!(filersmanager::file::validate_entry_name(&dir, "new").unwrap(),dir.join("new"));
+    assert!(filersmanager::file::validate_entry_name(&dir, "exists").is_err());
+    assert!(filersmanager::file::validate_entry_name(&dir, "a/b").is_err());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+//衝突したら常にactionで答えて転送を最後まで実行する
+fn run_transfer(kind:filersmanager::transfer::TransferKind,sources:Vec<PathBuf>,destination:PathBuf,action:filersmanager::transfer::ConflictAction,cancel:bool)->Result<(),filersmanager::error::Error>{
+    use filersmanager::transfer::{run, TransferEvent};
+    use iced::futures::{channel::mpsc, future::join, StreamExt};
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    let (sender,mut receiver) = mpsc::channel(16);
+    let cancel = Arc::new(AtomicBool::new(cancel));
+    let events = async move {
+        while let Some(event) = receiver.next().await {
+            if let TransferEvent::Conflict(_,responder) = event {
+                responder.respond(action, false);
+            }
+        }
+    };
+    block_on(join(run(kind, sources, destination, sender, cancel),events)).0
+}
+
+#[test]
+fn test_transfer()->O{
+    use filersmanager::{error::Error as E, transfer::{ConflictAction, TransferKind}};
+
+    let dir = temp_dir("transfer");
+    let dest = dir.join("dest");
+    fs::create_dir_all(dir.join("src/sub"))?;
+    fs::create_dir(&dest)?;
+    fs::write(dir.join("src/a.txt"), "new")?;
+    fs::write(dir.join("src/sub/b.txt"), "b")?;
+
+    //コピー
+    run_transfer(TransferKind::Copy, vec![dir.join("src/sub")], dest.clone(), ConflictAction::Skip, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("sub/b.txt"))?,"b");
+    assert!(dir.join("src/sub/b.txt").exists());
+
+    //中断したフォルダのコピーは作りかけを残さない
+    let result = run_transfer(TransferKind::Copy, vec![dir.join("src")], dest.clone(), ConflictAction::Skip, true);
+    assert!(matches!(result,Err(E::Cancelled)));
+    assert!(!dest.join("src").exists());
+
+    //スキップ
+    fs::write(dest.join("a.txt"), "old")?;
+    run_transfer(TransferKind::Copy, vec![dir.join("src/a.txt")], dest.clone(), ConflictAction::Skip, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("a.txt"))?,"old");
+
+    //中断しても上書き先は残る
+    let result = run_transfer(TransferKind::Copy, vec![dir.join("src/a.txt")], dest.clone(), ConflictAction::Overwrite, true);
+    assert!(matches!(result,Err(E::Cancelled)));
+    assert_eq!(fs::read_to_string(dest.join("a.txt"))?,"old");
+    assert_eq!(fs::read_dir(&dest)?.count(),2);
+
+    //上書き
+    run_transfer(TransferKind::Copy, vec![dir.join("src/a.txt")], dest.clone(), ConflictAction::Overwrite, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("a.txt"))?,"new");
+    assert_eq!(fs::read_dir(&dest)?.count(),2);
+
+    //移動して上書き
+    fs::write(dest.join("a.txt"), "old")?;
+    run_transfer(TransferKind::Move, vec![dir.join("src/a.txt")], dest.clone(), ConflictAction::Overwrite, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("a.txt"))?,"new");
+    assert!(!dir.join("src/a.txt").exists());
+
+    //転送元を含むディレクトリには上書きできない
+    fs::create_dir_all(dir.join("nest/nest"))?;
+    let result = run_transfer(TransferKind::Move, vec![dir.join("nest/nest")], dir.clone(), ConflictAction::Overwrite, false);
+    assert!(matches!(result,Err(E::InvalidDestination(_))));
+    assert!(dir.join("nest/nest").exists());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_transfer_cross_device()->O{
+    use filersmanager::transfer::{ConflictAction, TransferKind};
+    use std::os::unix::fs::MetadataExt;
+
+    //別のファイルシステムがなければrenameで移動できてしまうので確認できない
+    let other = Path::new("/dev/shm");
+    let dir = temp_dir("transfer_device");
+    if !other.is_dir() || fs::metadata(other)?.dev() == fs::metadata(&dir)?.dev() {
+        fs::remove_dir_all(&dir)?;
+        return Ok(());
+    }
+    let dest = other.join(format!("filersmanager_transfer_{}",std::process::id()));
+    let _ = fs::remove_dir_all(&dest);
+    fs::create_dir(&dest)?;
+    fs::create_dir(dir.join("sub"))?;
+    fs::write(dir.join("sub/a.txt"), "a")?;
+
+    run_transfer(TransferKind::Move, vec![dir.join("sub")], dest.clone(), ConflictAction::Skip, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("sub/a.txt"))?,"a");
+    assert!(!dir.join("sub").exists());
+
+    //別のファイルシステムへ移動して上書き
+    fs::create_dir(dir.join("sub"))?;
+    fs::write(dir.join("sub/a.txt"), "new")?;
+    run_transfer(TransferKind::Move, vec![dir.join("sub")], dest.clone(), ConflictAction::Overwrite, false).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("sub/a.txt"))?,"new");
+    assert!(!dir.join("sub").exists());
+    assert_eq!(fs::read_dir(&dest)?.count(),1);
+
+    fs::remove_dir_all(&dest)?;
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_batch_rename_preview()->O{
+    use filersmanager::batch_rename::{preview, BatchRenameOptions, CaseConversion, PreviewStatus};
+
+    let dir = temp_dir("batch_rename");
+    let paths = vec![dir.join("log_a.TXT"),dir.join("log_b.TXT")];
+    for path in &paths {
+        fs::write(path, "")?;
+    }
+    fs::write(dir.join("other.txt"), "")?;
+
+    let options = BatchRenameOptions{
+        find:"log_".into(),
+        template:"{name}_{n:03}".into(),
+        case:CaseConversion::Lower,
+        ..Default::default()
+    };
+    let previews = preview(&paths, &options).unwrap();
+    assert_eq!(previews[0].new_name(),"a_001.txt");
+    assert_eq!(previews[1].new_name(),"b_002.txt");
+    assert!(previews.iter().all(|p| p.status == PreviewStatus::Ok));
+
+    let options = BatchRenameOptions{
+        find:r"log_\w".into(),
+        replace:"other".into(),
+        use_regex:true,
+        case:CaseConversion::Lower,
+        ..Default::default()
+    };
+    let previews = preview(&paths, &options).unwrap();
+    assert!(previews.iter().all(|p| p.status == PreviewStatus::Collision));
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_batch_rename_apply()->O{
+    use filersmanager::batch_rename::{apply, preview, BatchRenameOptions, CaseConversion, PreviewStatus, RenamePreview};
+
+    let dir = temp_dir("batch_rename_apply");
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    fs::write(&a, "a")?;
+    fs::write(&b, "b")?;
+
+    //入れ替え
+    let previews = vec![
+        RenamePreview{from:a.clone(),to:b.clone(),status:PreviewStatus::Ok},
+        RenamePreview{from:b.clone(),to:a.clone(),status:PreviewStatus::Ok},
+    ];
+    assert_eq!(block_on(apply(previews)).unwrap().len(),2);
+    assert_eq!(fs::read_to_string(&a)?,"b");
+    assert_eq!(fs::read_to_string(&b)?,"a");
+    assert_eq!(fs::read_dir(&dir)?.count(),2);
+
+    //失敗したら適用済みの変更も戻す
+    let previews = vec![
+        RenamePreview{from:a.clone(),to:dir.join("c.txt"),status:PreviewStatus::Ok},
+        RenamePreview{from:dir.join("missing.txt"),to:dir.join("d.txt"),status:PreviewStatus::Ok},
+    ];
+    assert!(block_on(apply(previews)).is_err());
+    assert_eq!(fs::read_to_string(&a)?,"b");
+    assert!(!dir.join("c.txt").exists());
+
+    //大文字小文字だけの変更は、大文字小文字を区別しないFSでも自分自身とは衝突しない
+    fs::write(dir.join("e.TXT"), "e")?;
+    let options = BatchRenameOptions{case:CaseConversion::Lower,..Default::default()};
+    let previews = preview(&[dir.join("e.TXT")], &options).unwrap();
+    assert_eq!(previews[0].status,PreviewStatus::Ok);
+    block_on(apply(previews)).unwrap();
+    assert_eq!(fs::read_dir(&dir)?.filter_map(|e| e.ok()).filter(|e| e.file_name() == "e.txt").count(),1);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_shred_fill(){
+    use filersmanager::shred::fill;
+
+    let mut seed = 0x1234_5678_9abc_def1;
+    let mut buffer = [0x55u8;20];
+    fill(&mut buffer, 0, 3, &mut seed);
+    assert!(buffer.iter().any(|b| *b != 0x55));
+    fill(&mut buffer, 1, 3, &mut seed);
+    assert!(buffer.iter().all(|b| *b == 0xff));
+    //最終パスはゼロ
+    fill(&mut buffer, 2, 3, &mut seed);
+    assert!(buffer.iter().all(|b| *b == 0));
+    //1回だけなら乱数
+    fill(&mut buffer, 0, 1, &mut seed);
+    assert!(buffer.iter().any(|b| *b != 0));
+}
+
+//ハードリンク側から上書き後の中身を確認する
+fn run_shred(sources:Vec<PathBuf>,passes:u8,cancel:bool)->Result<(),filersmanager::error::Error>{
+    use iced::futures::{channel::mpsc, future::join, StreamExt};
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    let (sender,receiver) = mpsc::channel(16);
+    let cancel = Arc::new(AtomicBool::new(cancel));
+    block_on(join(filersmanager::shred::run(sources, passes, sender, cancel),receiver.collect::<Vec<_>>())).0
+}
+
+#[test]
+fn test_shred()->O{
+    let dir = temp_dir("shred");
+    fs::create_dir(dir.join("sub"))?;
+    fs::write(dir.join("a.txt"), "secret")?;
+    fs::write(dir.join("sub/b.txt"), "secret")?;
+    fs::hard_link(dir.join("a.txt"), dir.join("a_link"))?;
+    fs::hard_link(dir.join("sub/b.txt"), dir.join("b_link"))?;
+
+    run_shred(vec![dir.join("a.txt"),dir.join("sub")], 3, false).unwrap();
+    assert!(!dir.join("a.txt").exists());
+    assert!(!dir.join("sub").exists());
+    assert_eq!(fs::read(dir.join("a_link"))?,vec![0u8;6]);
+    assert_eq!(fs::read(dir.join("b_link"))?,vec![0u8;6]);
+
+    //中断しても上書きを始めたファイルは削除し、まだのファイルは残す
+    fs::write(dir.join("c.txt"), "secret")?;
+    fs::write(dir.join("d.txt"), "secret")?;
+    fs::hard_link(dir.join("c.txt"), dir.join("c_link"))?;
+    let result = run_shred(vec![dir.join("c.txt"),dir.join("d.txt")], 1, true);
+    assert!(matches!(result,Err(filersmanager::error::Error::Cancelled)));
+    assert!(!dir.join("c.txt").exists());
+    assert_ne!(fs::read(dir.join("c_link"))?,b"secret");
+    assert_eq!(fs::read(dir.join("d.txt"))?,b"secret");
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_table_shares(){
+    use filersmanager::{cli::SortKey, widget::{FileTableRow, TableState}};
+
+    let row = |name:&str,bytes:u64| FileTableRow::generate(PathBuf::from(name), bytes, bytes.to_string(), None, false);
+    let mut state = TableState::new(None);
+    state.set_rows(vec![row("a",60),row("b",40)]);
+    assert_eq!(state.rows.iter().map(FileTableRow::share).collect::<Vec<_>>(),vec![0.6,0.4]);
+
+    state.insert_sorted(row("c",100), SortKey::Size);
+    assert_eq!(state.rows.iter().map(FileTableRow::share).collect::<Vec<_>>(),vec![0.5,0.3,0.2]);
+
+    state.remove_row(0);
+    assert_eq!(state.rows.iter().map(FileTableRow::share).collect::<Vec<_>>(),vec![0.6,0.4]);
+
+    //名前順で表示しているときは名前の位置に入る
+    state.set_rows(vec![row("a",60),row("c",40)]);
+    state.insert_sorted(row("b",100), SortKey::Name);
+    assert_eq!(state.rows.iter().map(FileTableRow::file_name).collect::<Vec<_>>(),vec!["a","b","c"]);
+}
+
+#[test]
+fn test_squarify(){
+    use filersmanager::widget::treemap::squarify;
+    use iced::{Point, Rectangle, Size};
+
+    let bounds = Rectangle::new(Point::ORIGIN, Size::new(600.0, 400.0));
+    let rects = squarify(&[6,6,4,3,2,2,1], bounds);
+    assert_eq!(rects.len(),7);
+
+    let area:f32 = rects.iter().map(|r| r.width * r.height).sum();
+    assert!((area - 240000.0).abs() < 1.0);
+    for r in &rects {
+        assert!(r.x >= -0.01 && r.y >= -0.01);
+        assert!(r.x + r.width <= 600.01 && r.y + r.height <= 400.01);
+    }
+    //面積はサイズに比例する
+    assert!((rects[0].width * rects[0].height - 60000.0).abs() < 1.0);
+}
+
+#[test]
+fn test_scan_tree()->O{
+    let dir = temp_dir("scan");
+    fs::create_dir_all(dir.join("sub/deep"))?;
+    fs::write(dir.join("a.bin"), [0u8;10])?;
+    fs::write(dir.join("sub/b.bin"), [0u8;20])?;
+    fs::write(dir.join("sub/deep/c.bin"), [0u8;30])?;
+
+    let result = block_on(filersmanager::scan::scan(dir.clone()));
+    assert_eq!(result.tree.size,60);
+    assert_eq!(result.tree.file_count(),3);
+    assert_eq!(result.tree.children[0].name,"sub");
+    assert_eq!(result.node(&dir.join("sub/deep")).unwrap().size,30);
+
+    let children = result.children_of(&dir.join("sub")).unwrap();
+    assert_eq!(children[0].0,dir.join("sub/deep"));
+    //フォルダかどうかはディスクではなくツリーから返す
+    assert!(children[0].3);
+    assert!(!children[1].3);
+    //並べ替えに使う日時はCLIと同じ更新日時
+    assert_eq!(children[1].2,result.node(&children[1].0).unwrap().modified);
+    assert!(children[1].2.is_some());
+
+    //ツリーを書き換えるたびにrevisionが変わる
+    let mut result = (*result).clone();
+    assert_eq!(result.revision,0);
+    result.rename(&dir.join("a.bin"), &dir.join("b.bin"));
+    result.remove(&dir.join("sub/b.bin"));
+    assert_eq!(result.revision,2);
+    assert_eq!(result.tree.size,40);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_scan_one_file_system()->O{
+    use filersmanager::scan::{scan_with, ScanOptions};
+    use std::os::unix::fs::MetadataExt;
+
+    //別のファイルシステムがマウントされたフォルダがなければ確認できない
+    let (dev,shm) = (Path::new("/dev"),Path::new("/dev/shm"));
+    if !shm.is_dir() || fs::metadata(dev)?.dev() == fs::metadata(shm)?.dev() {
+        return Ok(());
+    }
+    let marker = shm.join(format!("filersmanager_mount_{}",std::process::id()));
+    fs::write(&marker, "data")?;
+
+    let options = ScanOptions{one_file_system:true,..Default::default()};
+    let result = block_on(scan_with(dev.to_path_buf(), options));
+    assert!(result.node(shm).unwrap().children.is_empty());
+    let result = block_on(scan_with(dev.to_path_buf(), ScanOptions::default()));
+    assert!(result.contains(&marker));
+
+    fs::remove_file(&marker)?;
+    Ok(())
+}
+
+//tokio::fsを使う処理があるのでtokioのランタイムで実行する
+fn block_on<F:Future>(future:F)->F::Output{
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+}
+
+fn node(name:&str,size:u64,children:Vec<filersmanager::scan::ScanNode>)->filersmanager::scan::ScanNode{
+    filersmanager::scan::ScanNode{
+        name:name.into(),
+        size,
+        is_dir:!children.is_empty(),
+        mode:0o644,
+        modified:None,
+        accessed:None,
+        children,
+    }
+}
+
+#[test]
+fn test_sunburst_layout(){
+    use filersmanager::widget::{sunburst::layout, treemap::ColorMode};
+
+    let tree = node("root",100,vec![
+        node("dir",75,vec![node("a",50,vec![]),node("b",25,vec![])]),
+        node("c",25,vec![]),
+    ]);
+    let segments = layout(&tree, Path::new("/root"), 2, ColorMode::FileType, std::time::SystemTime::now());
+    assert_eq!(segments.len(),4);
+
+    let top:f32 = segments.iter().filter(|s| s.depth == 0).map(|s| s.end - s.start).sum();
+    assert!((top - std::f32::consts::TAU).abs() < 1e-4);
+    //子の扇形は親の範囲に収まる
+    let dir = &segments[0];
+    assert_eq!(dir.path,PathBuf::from("/root/dir"));
+    for child in segments.iter().filter(|s| s.depth == 1) {
+        assert!(child.start >= dir.start - 1e-4 && child.end <= dir.end + 1e-4);
+    }
+
+    let shallow = layout(&tree, Path::new("/root"), 1, ColorMode::FileType, std::time::SystemTime::now());
+    assert_eq!(shallow.len(),2);
+}
+
+#[test]
+fn test_type_breakdown(){
+    use filersmanager::{analysis::{files_matching, type_breakdown, TypeFilter}, filetype::FileType};
+
+    let tree = node("root",100,vec![
+        node("src",30,vec![node("main.rs",20,vec![]),node("Clip.MP4",10,vec![])]),
+        node("movie.mp4",60,vec![]),
+        node("README",10,vec![]),
+    ]);
+    let breakdown = type_breakdown(&tree, Path::new("/root"));
+    assert_eq!(breakdown.total,100);
+    let video = &breakdown.categories[0];
+    assert_eq!(video.filter,TypeFilter::Category(FileType::Video));
+    assert_eq!((video.count,video.bytes),(2,70));
+    assert!((breakdown.percentage(video) - 70.0).abs() < 1e-9);
+    //拡張子は小文字にまとめる
+    assert_eq!(breakdown.extensions[0].filter,TypeFilter::Extension("mp4".into()));
+    assert!(breakdown.extensions.iter().any(|group| group.filter == TypeFilter::Extension(String::new())));
+
+    let files:Vec<PathBuf> = files_matching(&tree, Path::new("/root"), &TypeFilter::Extension("mp4".into()))
+        .into_iter().map(|(path,_,_,_)| path).collect();
+    assert_eq!(files,vec![PathBuf::from("/root/src/Clip.MP4"),PathBuf::from("/root/movie.mp4")]);
+}
+
+#[test]
+fn test_age_histogram(){
+    use filersmanager::analysis::{age_histogram, stale_files, AgeBucket};
+    use std::time::{Duration, SystemTime};
+
+    let now = SystemTime::now();
+    let days = |n:u64| Some(now - Duration::from_secs(n * 24 * 60 * 60));
+    let mut tree = node("root",0,vec![
+        node("dir",0,vec![node("old.iso",500,vec![]),node("small.txt",5,vec![])]),
+        node("new.txt",10,vec![]),
+        node("month.log",40,vec![]),
+    ]);
+    tree.children[0].children[0].modified = days(400);
+    tree.children[0].children[1].modified = days(400);
+    tree.children[1].modified = Some(now);
+    tree.children[2].modified = days(10);
+    tree.update_size();
+
+    let histogram = age_histogram(&tree, now);
+    assert_eq!(histogram.total,555);
+    let bytes = |bucket| histogram.groups.iter().find(|g| g.bucket == bucket).map(|g| g.bytes);
+    assert_eq!(bytes(AgeBucket::Day),Some(10));
+    assert_eq!(bytes(AgeBucket::Month),Some(40));
+    assert_eq!(bytes(AgeBucket::Older),Some(505));
+    //更新日時が全て取れていればUnknownは出さない
+    assert_eq!(bytes(AgeBucket::Unknown),None);
+
+    let stale:Vec<PathBuf> = stale_files(&tree, Path::new("/root"), now, 365, 100)
+        .into_iter().map(|(path,_,_,_)| path).collect();
+    assert_eq!(stale,vec![PathBuf::from("/root/dir/old.iso")]);
+    assert_eq!(stale_files(&tree, Path::new("/root"), now, 7, 0).len(),3);
+}
+
+#[test]
+fn test_largest_files(){
+    use filersmanager::analysis::largest_files;
+
+    let tree = node("root",0,vec![
+        node("a",0,vec![node("b",0,vec![node("huge.bin",4000,vec![])]),node("x",30,vec![])]),
+        node("y",300,vec![]),
+        node("z",3,vec![]),
+    ]);
+    let top:Vec<(PathBuf,u64)> = largest_files(&tree, Path::new("/r"), 2)
+        .into_iter().map(|(path,size,_,_)| (path,size)).collect();
+    assert_eq!(top,vec![(PathBuf::from("/r/a/b/huge.bin"),4000),(PathBuf::from("/r/y"),300)]);
+    assert_eq!(largest_files(&tree, Path::new("/r"), 10).len(),4);
+    assert!(largest_files(&tree, Path::new("/r"), 0).is_empty());
+}
+
+#[test]
+fn test_largest_files_on_disk()->O{
+    use filersmanager::scan::{largest_files, ScanOptions};
+
+    let dir = temp_dir("largest");
+    fs::create_dir_all(dir.join("a/b"))?;
+    fs::create_dir(dir.join("skip"))?;
+    fs::write(dir.join("a/b/huge.bin"), [0u8;400])?;
+    fs::write(dir.join("a/x.bin"), [0u8;30])?;
+    fs::write(dir.join("y.bin"), [0u8;300])?;
+    fs::write(dir.join("skip/z.bin"), [0u8;1000])?;
+
+    let options = ScanOptions{exclude:vec!["^skip$".into()],..Default::default()};
+    let top:Vec<(PathBuf,u64)> = block_on(largest_files(dir.clone(), 2, options))
+        .into_iter().map(|(path,size,_,_)| (path,size)).collect();
+    assert_eq!(top,vec![(dir.join("a/b/huge.bin"),400),(dir.join("y.bin"),300)]);
+    assert!(block_on(largest_files(dir.clone(), 0, ScanOptions::default())).is_empty());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_find()->O{
+    use filersmanager::duplicate::{find, resolve, DuplicateAction};
+    use iced::futures::channel::mpsc;
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    let dir = temp_dir("duplicate");
+    //先頭64KiBが同じで末尾だけ異なるファイルは全体のハッシュで区別される
+    let mut content = vec![7u8; 70 * 1024];
+    fs::write(dir.join("a.bin"), &content)?;
+    fs::write(dir.join("b.bin"), &content)?;
+    *content.last_mut().unwrap() = 8;
+    fs::write(dir.join("c.bin"), &content)?;
+    let size = content.len() as u64;
+
+    let (sender,_receiver) = mpsc::channel(100);
+    let candidates = vec![(size,vec![dir.join("c.bin"),dir.join("b.bin"),dir.join("a.bin")])];
+    let groups = block_on(find(candidates, sender, Arc::new(AtomicBool::new(false)))).unwrap();
+    assert_eq!(groups.len(),1);
+    assert_eq!(groups[0].files,vec![dir.join("a.bin"),dir.join("b.bin")]);
+    assert_eq!(groups[0].wasted(),size);
+
+    //検索後に残すファイルが書き換えられた組は処理しない
+    fs::write(dir.join("a.bin"), &content)?;
+    block_on(resolve(groups.clone(), DuplicateAction::Trash)).unwrap();
+    assert!(dir.join("b.bin").exists());
+    //残すファイルが消えていても処理しない
+    fs::rename(dir.join("a.bin"), dir.join("moved.bin"))?;
+    block_on(resolve(groups.clone(), DuplicateAction::Trash)).unwrap();
+    assert!(dir.join("b.bin").exists());
+    fs::rename(dir.join("moved.bin"), dir.join("a.bin"))?;
+    *content.last_mut().unwrap() = 7;
+    fs::write(dir.join("a.bin"), &content)?;
+
+    #[cfg(unix)]
+    {
+        use filersmanager::duplicate::group_by_size;
+        use std::os::unix::fs::MetadataExt;
+        block_on(resolve(groups, DuplicateAction::HardLink)).unwrap();
+        assert_eq!(fs::metadata(dir.join("a.bin"))?.ino(),fs::metadata(dir.join("b.bin"))?.ino());
+        assert_eq!(fs::read_dir(&dir)?.count(),3);
+
+        //ハードリンク同士は重複として扱わない
+        let result = block_on(filersmanager::scan::scan(dir.clone()));
+        let candidates = group_by_size(&result.tree, &dir);
+        assert_eq!(candidates[0].1.len(),2);
+        let (sender,_receiver) = mpsc::channel(100);
+        assert!(block_on(find(candidates, sender, Arc::new(AtomicBool::new(false)))).unwrap().is_empty());
+    }
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_empty_entries()->O{
+    use filersmanager::{analysis::empty_entries, file::remove_empty_entries, scan::scan};
+
+    let dir = temp_dir("empty");
+    fs::create_dir_all(dir.join("chain/a/b"))?;
+    fs::create_dir_all(dir.join("keep/empty"))?;
+    fs::write(dir.join("keep/data.txt"), "data")?;
+    fs::write(dir.join("keep/zero.txt"), "")?;
+
+    let result = block_on(scan(dir.clone()));
+    let entries = empty_entries(&result.tree, &dir);
+    let mut dirs = entries.dirs.clone();
+    dirs.sort();
+    assert_eq!(dirs,vec![dir.join("chain"),dir.join("keep/empty")]);
+    assert_eq!(entries.files,vec![dir.join("keep/zero.txt")]);
+
+    block_on(remove_empty_entries(entries.dirs, entries.files)).unwrap();
+    assert!(!dir.join("chain").exists());
+    assert!(!dir.join("keep/zero.txt").exists());
+    assert!(dir.join("keep/data.txt").exists());
+
+    //削除できないものがあっても残りは削除し、失敗したものを返す
+    fs::create_dir_all(dir.join("later"))?;
+    fs::create_dir_all(dir.join("empty"))?;
+    let entries = empty_entries(&block_on(scan(dir.clone())).tree, &dir);
+    fs::write(dir.join("later/new.txt"), "new")?;
+    match block_on(remove_empty_entries(entries.dirs, entries.files)) {
+        Err(filersmanager::error::Error::RemoveFailed(failed))=>assert_eq!(failed,vec![dir.join("later")]),
+        result=>panic!("{:?}",result),
+    }
+    assert!(!dir.join("empty").exists());
+    assert!(dir.join("later/new.txt").exists());
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_junk_report(){
+    use filersmanager::junk::{report, JunkConfig};
+
+    let config = JunkConfig::parse(r#"
+disabled = ["Cache folders"]
+
+[[rule]]
+name = "Backups"
+pattern = '\.bak$'
+"#).unwrap();
+    let mut tree = node("root",0,vec![
+        node("rust",0,vec![node("Cargo.toml",1,vec![]),node("target",0,vec![node("big",500,vec![])])]),
+        //Cargo.tomlがなければtargetは対象外
+        node("other",0,vec![node("target",0,vec![node("keep",70,vec![])])]),
+        node("web",0,vec![node("node_modules",0,vec![node("node_modules",0,vec![node("x",30,vec![])])])]),
+        node(".cache",0,vec![node("y",9,vec![])]),
+        node("old.bak",4,vec![]),
+    ]);
+    tree.update_size();
+
+    let report = report(&tree, Path::new("/r"), &config);
+    assert_eq!(report.total,534);
+    let summary = |name:&str| report.rules.iter().find(|s| s.rule.name == name).unwrap();
+    assert_eq!((summary("Rust target").count,summary("Rust target").bytes),(1,500));
+    assert_eq!(summary("node_modules").count,1);
+    assert!(!summary("Cache folders").enabled);
+    assert_eq!(summary("Cache folders").bytes,0);
+    assert_eq!(summary("Backups").bytes,4);
+
+    assert!(JunkConfig::parse("disabled = 1").is_err());
+}
+
+#[test]
+fn test_parse_mountinfo(){
+    use filersmanager::volume::parse_mountinfo;
+
+    let text = "22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n\
+                45 22 0:40 / /media/usb\\040disk rw,nosuid master:3 - vfat /dev/sdb1 rw,fmask=0022\n\
+                broken line";
+    let mounts = parse_mountinfo(text);
+    assert_eq!(mounts.len(),2);
+    assert_eq!(mounts[0].mount_point,PathBuf::from("/"));
+    assert_eq!(mounts[0].fs_type,"ext4");
+    assert_eq!(mounts[1].mount_point,PathBuf::from("/media/usb disk"));
+    assert_eq!(mounts[1].source,"/dev/sdb1");
+
+    //statvfsは別スレッドで呼ぶので、容量を取得できたものだけが並ぶ
+    let volumes = block_on(filersmanager::volume::volumes());
+    assert!(volumes.iter().all(|volume| volume.total > 0 && volume.free <= volume.total));
+}
+
+#[test]
+fn test_export_formats()->O{
+    use filersmanager::export::{csv_field, render, ExportEntry, ExportFormat, SCHEMA};
+
+    assert_eq!(csv_field("plain"),"plain");
+    assert_eq!(csv_field("a,b"),"\"a,b\"");
+    assert_eq!(csv_field("say \"hi\"\n"),"\"say \"\"hi\"\"\n\"");
+    assert_eq!(ExportFormat::from_path(Path::new("out.JSON")),Some(ExportFormat::Json));
+    assert_eq!(ExportFormat::from_path(Path::new("out.txt")),None);
+
+    let dir = temp_dir("export");
+    let file = dir.join("a,b.txt");
+    fs::write(&file, "12345")?;
+    let entries = vec![ExportEntry::new(file.clone(), 5), ExportEntry::new(dir.clone(), 5)];
+    assert_eq!(entries[0].entry_type,"file");
+    assert_eq!(entries[1].entry_type,"dir");
+    assert!(entries[0].modified.is_some());
+
+    let csv = render(ExportFormat::Csv, &entries).unwrap();
+    let lines:Vec<&str> = csv.split("\r\n").collect();
+    assert_eq!(lines[0],"path,name,type,bytes,permissions,modified,accessed,created");
+    assert!(lines[1].starts_with(&format!("\"{}\",\"a,b.txt\",file,5,", file.display())));
+
+    let json:serde_json::Value = serde_json::from_str(&render(ExportFormat::Json, &entries).unwrap())?;
+    assert_eq!(json["schema"],SCHEMA);
+    assert_eq!(json["entries"][0]["bytes"],5);
+    assert_eq!(json["entries"][0]["type"],"file");
+
+    //手元にないファイルはツリーの情報から作る
+    let mut tree = node("gone.txt",7,vec![]);
+    tree.mode = 0o600;
+    let entry = ExportEntry::from_node(dir.join("gone.txt"), &tree);
+    assert_eq!((entry.entry_type,entry.bytes,entry.permissions.as_str()),("file",7,"0600"));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_html_report(){
+    use filersmanager::{report::{escape, html_report}, scan::ScanResult, widget::treemap::ColorMode};
+    use std::time::SystemTime;
+
+    assert_eq!(escape("<a href=\"x\">&</a>"),"&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    let mut tree = node("root",0,vec![
+        node("dir",0,vec![node("big.mp4",900,vec![])]),
+        node("<script>.txt",100,vec![]),
+    ]);
+    tree.update_size();
+    let result = ScanResult{root:PathBuf::from("/r"),scanned_at:SystemTime::now(),tree,revision:0};
+    let html = html_report(&result, Path::new("/r"), ColorMode::FileType, SystemTime::now());
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;script&gt;.txt"));
+    assert!(!html.contains("<script>.txt"));
+    assert!(html.contains("dir/big.mp4"));
+    assert!(html.contains("<svg"));
+    assert!(html.matches("<rect").count() >= 3);
+}
+
+#[test]
+fn test_ncdu_roundtrip(){
+    use filersmanager::ncdu::{export, import};
+
+    let text = r#"[1,2,{"progname":"ncdu","progver":"1.19","timestamp":1700000000},
+        [{"name":"/srv","asize":4096,"dsize":4096},
+         {"name":"a.log","asize":100,"dsize":4096,"mtime":1690000000,"mode":33188},
+         [{"name":"sub","asize":4096},{"name":"b.bin","asize":900}],
+         {"name":"fifo","notreg":true},
+         {"name":"tmp","excluded":"pattern"}]]"#;
+    let result = import(text).unwrap();
+    assert_eq!(result.root,PathBuf::from("/srv"));
+    assert_eq!(result.tree.size,1000);
+    assert_eq!(result.tree.children.len(),2);
+    //サイズ順に並べ直される
+    assert_eq!(result.tree.children[0].name,"sub");
+    let log = result.node(Path::new("/srv/a.log")).unwrap();
+    assert_eq!(log.mode,0o644);
+    assert!(log.modified.is_some());
+
+    let again = import(&export(&result)).unwrap();
+    assert_eq!(again.root,result.root);
+    assert_eq!(again.tree.size,1000);
+    assert_eq!(again.node(Path::new("/srv/a.log")).unwrap().mode,0o644);
+    assert_eq!(again.scanned_at,result.scanned_at);
+
+    assert!(import("[2,0,{}]").is_err());
+    assert!(import("{}").is_err());
+}
+
+#[test]
+fn test_snapshot_roundtrip(){
+    use filersmanager::{scan::{ScanOptions, ScanResult}, snapshot::{from_bytes, to_bytes, Snapshot}};
+    use std::time::{Duration, SystemTime};
+
+    let mut tree = node("root",0,vec![node("dir",0,vec![node("a",10,vec![])]),node("b",5,vec![])]);
+    tree.children[1].modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    tree.update_size();
+    let scanned_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+    let result = ScanResult{root:PathBuf::from("/data"),scanned_at,tree,revision:0};
+
+    let options = ScanOptions{exclude:vec![r"^\.git$".into()],one_file_system:true};
+    let bytes = to_bytes(&Snapshot::new(result, options.clone())).unwrap();
+    //gzipのマジックナンバー
+    assert_eq!(&bytes[..2],&[0x1f,0x8b]);
+    let snapshot = from_bytes(&bytes).unwrap();
+    assert_eq!(snapshot.options,options);
+    assert_eq!(snapshot.result.root,PathBuf::from("/data"));
+    assert_eq!(snapshot.result.scanned_at,scanned_at);
+    assert_eq!(snapshot.result.tree.size,15);
+    assert_eq!(snapshot.result.node(Path::new("/data/b")).unwrap().modified,Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+    assert!(from_bytes(b"not a snapshot").is_err());
+}
+
+#[test]
+fn test_scan_diff(){
+    use filersmanager::{diff::{diff, ChangeKind}, scan::ScanResult};
+    use std::time::SystemTime;
+
+    let result = |mut tree:filersmanager::scan::ScanNode| {
+        tree.update_size();
+        ScanResult{root:PathBuf::from("/d"),scanned_at:SystemTime::now(),tree,revision:0}
+    };
+    let old = result(node("root",0,vec![
+        node("logs",0,vec![node("a.log",100,vec![])]),
+        node("gone",0,vec![node("x",50,vec![]),node("y",50,vec![])]),
+        node("same.txt",10,vec![]),
+    ]));
+    let new = result(node("root",0,vec![
+        node("logs",0,vec![node("a.log",100,vec![]),node("b.log",900,vec![])]),
+        node("same.txt",10,vec![]),
+        node("video.mp4",300,vec![]),
+    ]));
+
+    let report = diff(&old, &new).unwrap();
+    assert_eq!(report.root.delta(),1310 - 210);
+    let summary:Vec<(PathBuf,ChangeKind,i64)> = report.entries.iter().map(|e| (e.path.clone(),e.kind,e.delta())).collect();
+    assert_eq!(summary,vec![
+        (PathBuf::from("/d/logs"),ChangeKind::Changed,900),
+        (PathBuf::from("/d/logs/b.log"),ChangeKind::Added,900),
+        (PathBuf::from("/d/video.mp4"),ChangeKind::Added,300),
+        (PathBuf::from("/d/gone"),ChangeKind::Removed,-100),
+    ]);
+    assert_eq!(report.entries[3].file_delta(),-2);
+
+    let other = ScanResult{root:PathBuf::from("/other"),..new.clone()};
+    assert!(diff(&old, &other).is_err());
+}
+
+#[test]
+fn test_cli_args(){
+    use filersmanager::{cli::{collect, parse_args, CliError, SortKey}, export::ExportFormat};
+
+    let args = |line:&str| parse_args(line.split_whitespace().map(String::from));
+    assert_eq!(args(""),Ok(None));
+    assert_eq!(args("--help"),Err(CliError::Help));
+    //サブコマンドでなければGUIで開くパスとして扱う
+    assert_eq!(args("/data/report.txt"),Ok(None));
+    let command = args("scan /data --format json --depth 3 --top 10 --sort name").unwrap().unwrap();
+    assert_eq!(command.path,PathBuf::from("/data"));
+    assert_eq!(command.format,ExportFormat::Json);
+    assert_eq!((command.depth,command.top,command.sort),(3,Some(10),SortKey::Name));
+    assert!(matches!(args("scan"),Err(CliError::Usage(_))));
+    assert!(matches!(args("scan /a --depth x"),Err(CliError::Usage(_))));
+    assert!(matches!(args("scan /a --format xml"),Err(CliError::Usage(_))));
+    assert!(matches!(args("scan /a --top"),Err(CliError::Usage(_))));
+    assert!(args("scan /a --largest --top 5").unwrap().unwrap().largest);
+
+    let mut tree = node("root",0,vec![node("dir",0,vec![node("deep",0,vec![node("x",70,vec![])]),node("b",20,vec![])]),node("a",50,vec![])]);
+    tree.update_size();
+    let paths = |entries:Vec<(PathBuf,u64,Option<std::time::SystemTime>,bool)>| entries.into_iter().map(|e| e.0).collect::<Vec<_>>();
+    assert_eq!(paths(collect(&tree, Path::new("/r"), 1, SortKey::Size, None)),vec![PathBuf::from("/r/dir"),PathBuf::from("/r/a")]);
+    assert_eq!(collect(&tree, Path::new("/r"), 2, SortKey::Size, None).len(),4);
+    assert_eq!(paths(collect(&tree, Path::new("/r"), 2, SortKey::Name, Some(2))),vec![PathBuf::from("/r/a"),PathBuf::from("/r/dir")]);
+}
+
+#[test]
+fn test_format_size(){
+    use filersmanager::units::{format_size_as, UnitStyle};
+
+    assert_eq!(format_size_as(0, UnitStyle::Iec),"0 B");
+    assert_eq!(format_size_as(1023, UnitStyle::Iec),"1023 B");
+    assert_eq!(format_size_as(1024, UnitStyle::Iec),"1.00 KiB");
+    assert_eq!(format_size_as(1536, UnitStyle::Iec),"1.50 KiB");
+    //四捨五入で1024.00 KiBにならず次の単位に繰り上がる
+    assert_eq!(format_size_as(1024 * 1024 - 1, UnitStyle::Iec),"1.00 MiB");
+    assert_eq!(format_size_as(5 << 40, UnitStyle::Iec),"5.00 TiB");
+    assert_eq!(format_size_as(3 << 50, UnitStyle::Iec),"3.00 PiB");
+    assert_eq!(format_size_as(u64::MAX, UnitStyle::Iec),"16.00 EiB");
+    //f32では失われる桁も保たれる
+    assert_eq!(format_size_as(16_777_217 * 1024, UnitStyle::Iec),"16.00 GiB");
+    assert_eq!(format_size_as(1_073_741_823 * 1024 + 10_737_418_240, UnitStyle::Iec),"1.01 TiB");
+
+    assert_eq!(format_size_as(999, UnitStyle::Si),"999 B");
+    assert_eq!(format_size_as(1000, UnitStyle::Si),"1.00 kB");
+    assert_eq!(format_size_as(999_999, UnitStyle::Si),"1.00 MB");
+    assert_eq!(format_size_as(2_500_000_000_000, UnitStyle::Si),"2.50 TB");
+
+    assert_eq!(format_size_as(999, UnitStyle::Bytes),"999 B");
+    assert_eq!(format_size_as(1_234_567, UnitStyle::Bytes),"1,234,567 B");
+    assert_eq!(format_size_as(u64::MAX, UnitStyle::Bytes),"18,446,744,073,709,551,615 B");
+}
+
+#[test]
+fn test_settings()->O{
+    use filersmanager::{cli::SortKey, settings::AppSettings, units::UnitStyle, widget::ColumnKind};
+
+    let settings = AppSettings::parse(r#"
+theme = "Light"
+unit_style = "si"
+sort = "name"
+exclude = ['^\.git$']
+
+[window]
+width = 800.0
+height = 600.0
+
+[[column]]
+kind = "size"
+width = 120.0
+
+[[column]]
+kind = "file_name"
+width = 300.0
+visible = false
+"#).unwrap();
+    assert_eq!(settings.theme,"Light");
+    assert_eq!((settings.unit_style,settings.sort),(UnitStyle::Si,SortKey::Name));
+    assert_eq!((settings.window.width,settings.window.x),(800.0,None));
+    //書かれていない列は既定の設定で末尾に追加される
+    assert_eq!(settings.columns.len(),ColumnKind::ALL.len());
+    assert_eq!(settings.columns[0].kind,ColumnKind::Size);
+    assert!(!settings.columns[1].visible);
+    assert!(settings.columns[2].visible);
+
+    let text = toml::to_string(&settings)?;
+    assert_eq!(AppSettings::parse(&text).unwrap(),settings);
+    assert_eq!(AppSettings::parse("").unwrap(),AppSettings::default());
+    assert!(AppSettings::parse("sort = \"color\"").is_err());
+
+    let dir = temp_dir("exclude");
+    fs::create_dir_all(dir.join(".git"))?;
+    fs::write(dir.join(".git/objects"), [0u8;40])?;
+    fs::write(dir.join("a.bin"), [0u8;10])?;
+    let result = block_on(filersmanager::scan::scan_excluding(dir.clone(), settings.exclude.clone()));
+    assert_eq!(result.tree.size,10);
+    assert!(result.node(&dir.join(".git")).is_none());
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_theme_settings(){
+    use filersmanager::{filetype::FileType, settings::AppSettings, theme::{apply_colors, parse_hex, to_hex, ThemeChoice}};
+    use iced::{Color, Theme};
+
+    assert_eq!(ThemeChoice::from_name("Nord"),ThemeChoice::BuiltIn(Theme::Nord));
+    assert_eq!(ThemeChoice::from_name("System"),ThemeChoice::System);
+    assert_eq!(ThemeChoice::from_name("unknown"),ThemeChoice::BuiltIn(Theme::Dark));
+    assert!(ThemeChoice::all().len() > Theme::ALL.len());
+
+    assert_eq!(parse_hex("#ff8000").unwrap(),Color::from_rgb8(0xff,0x80,0x00));
+    assert_eq!(to_hex(parse_hex("12abEF").unwrap()),"#12abef");
+    assert!(parse_hex("#12345").is_err());
+    assert!(parse_hex("#gg0000").is_err());
+
+    let settings = AppSettings::parse(r##"
+theme = "Custom"
+
+[palette]
+name = "Paper"
+background = "#fafafa"
+primary = "#3366cc"
+
+[colors]
+video = "#010203"
+image = "broken"
+"##).unwrap();
+    assert_eq!(settings.palette.background,"#fafafa");
+    let theme = ThemeChoice::from_name(&settings.theme).resolve(&settings.palette);
+    assert_eq!(theme.to_string(),"Paper");
+    assert_eq!(theme.palette().primary,Color::from_rgb8(0x33,0x66,0xcc));
+
+    apply_colors(&settings.colors);
+    assert_eq!(FileType::Video.color(),Color::from_rgb8(1,2,3));
+    assert_eq!(FileType::Image.color(),FileType::Image.default_color());
+    apply_colors(&Default::default());
+    assert_eq!(FileType::Video.color(),FileType::Video.default_color());
+}
+
+#[test]
+fn test_i18n(){
+    use filersmanager::{i18n::Language, settings::AppSettings};
+    use std::collections::HashSet;
+
+    //どちらの言語にも同じキーがそろっている
+    let keys = |language:Language| language.catalog().iter().map(|(key,_)| *key).collect::<HashSet<_>>();
+    assert_eq!(keys(Language::En),keys(Language::Ja));
+    assert_eq!(keys(Language::En).len(),Language::En.catalog().len());
+    //置き換える引数の数もそろっている
+    for (key,text) in Language::En.catalog(){
+        assert_eq!(text.matches("{}").count(),Language::Ja.tr(key).matches("{}").count(),"{}",key);
+    }
+
+    assert_eq!(Language::from_locale("ja_JP.UTF-8"),Language::Ja);
+    assert_eq!(Language::from_locale("en_US.UTF-8"),Language::En);
+    assert_eq!(Language::from_locale("C"),Language::En);
+
+    assert_eq!(Language::Ja.tr("button.cancel"),"キャンセル");
+    assert_eq!(Language::En.tr("no.such.key"),"no.such.key");
+    assert_eq!(Language::Ja.tr_fmt("transfer.exists",&[&"a.txt"]),"a.txtは既に存在します");
+    assert_eq!(Language::En.tr_fmt("volumes.usage",&[&1,&2,&3]),"1 / 2  free: 3");
+    assert_eq!(Language::Ja.tr("filter.no_extension"),"(拡張子なし)");
+    assert_eq!(Language::Ja.tr_fmt("filter.largest",&[&10]),"大きい順に10件");
+    assert_eq!(filersmanager::error::Error::AsyncTokioIoError(std::io::ErrorKind::NotFound).to_string(),"not found");
+
+    assert_eq!(AppSettings::parse("language = \"ja\"").unwrap().language,Some(Language::Ja));
+    assert_eq!(AppSettings::parse("").unwrap().language,None);
+}